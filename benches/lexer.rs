@@ -0,0 +1,164 @@
+/*
+ * 词法解析器性能测试
+ *
+ * 比较 LexerState（每个状态编译为一个 RegexSet）与逐个尝试
+ * 每个 Regex 的循环在同一组规则下的解析速度。
+ *
+ * 运行：cargo bench --bench lexer
+ */
+
+use hoya::{LexerState, Token, TokenFactory};
+use regex::Regex;
+use std::time::{Duration, Instant};
+
+const KEYWORDS: &[&str] = &[
+    "break", "case", "const", "continue", "default", "do", "else", "enum", "extern", "for", "goto",
+    "if", "return", "sizeof", "static", "struct", "switch", "typedef", "union", "while",
+];
+
+const RULES: &[(&str, &str)] = &[
+    (r"^[a-zA-Z_][a-zA-Z0-9_]*", "name"),
+    (r"^0[xX][0-9a-fA-F]+", "hex"),
+    (r"^\d+\.\d+", "float"),
+    (r"^\d+", "number"),
+    (r#"^"(?:\\"|[^"])*""#, "string"),
+    (r"^(==|!=|<=|>=|&&|\|\||<<|>>|\+\+|--|->)", "operator"),
+    (r"^[-+*/%<>=!&|^~?:]", "operator"),
+    (r"^[(){}\[\];,.]", "punctuation"),
+];
+
+const ITERATIONS: u32 = 5;
+
+// 改用 RegexSet 之前 LexerTokenFactory::get 的实现：逐个尝试每个规则
+struct SequentialLexer {
+    ignore: Regex,
+    rules: Vec<(Regex, &'static str)>,
+}
+
+impl SequentialLexer {
+    fn new() -> Self {
+        let mut rules: Vec<(Regex, &'static str)> = KEYWORDS
+            .iter()
+            .map(|keyword| (Regex::new(&format!(r"^{}\b", keyword)).unwrap(), "keyword"))
+            .collect();
+        rules.extend(
+            RULES
+                .iter()
+                .map(|(re, token_type)| (Regex::new(re).unwrap(), *token_type)),
+        );
+
+        SequentialLexer {
+            ignore: Regex::new(r"^\s+").unwrap(),
+            rules,
+        }
+    }
+
+    fn count(&self, src: &str) -> usize {
+        let mut offset = 0;
+        let mut count = 0;
+        while offset < src.len() {
+            if let Some(matched) = self.ignore.find(&src[offset..]) {
+                offset += matched.end();
+                continue;
+            }
+
+            let (token, _) = self
+                .rules
+                .iter()
+                .find_map(|(re, token_type)| {
+                    re.find(&src[offset..])
+                        .map(|matched| (matched.as_str(), *token_type))
+                })
+                .expect("sequential lexer failed");
+            offset += token.len();
+            count += 1;
+        }
+
+        count
+    }
+}
+
+fn new_lexer_state() -> LexerState<()> {
+    let mut state = LexerState::new("init", ());
+    state
+        .set_eof(|_, _| Token::new("eof", ""))
+        .set_ignore(r"^\s+");
+
+    let mut setter = state.state("init");
+    for keyword in KEYWORDS {
+        setter.token(&format!(r"^{}\b", keyword), |_, token| {
+            TokenFactory::new("keyword").build(token)
+        });
+    }
+    setter
+        .token(RULES[0].0, |_, token| {
+            TokenFactory::new("name").build(token)
+        })
+        .token(RULES[1].0, |_, token| TokenFactory::new("hex").build(token))
+        .token(RULES[2].0, |_, token| {
+            TokenFactory::new("float").build(token)
+        })
+        .token(RULES[3].0, |_, token| {
+            TokenFactory::new("number").build(token)
+        })
+        .token(RULES[4].0, |_, token| {
+            TokenFactory::new("string").build(token)
+        })
+        .token(RULES[5].0, |_, token| {
+            TokenFactory::new("operator").build(token)
+        })
+        .token(RULES[6].0, |_, token| {
+            TokenFactory::new("operator").build(token)
+        })
+        .token(RULES[7].0, |_, token| {
+            TokenFactory::new("punctuation").build(token)
+        });
+
+    state
+}
+
+fn count_lexer_state(src: &str) -> usize {
+    let mut state = new_lexer_state();
+    let mut count = 0;
+    loop {
+        let token = state.next(src).expect("lexer state failed");
+        if token.get_type().eq("eof") {
+            break count;
+        }
+        count += 1;
+    }
+}
+
+fn source() -> String {
+    let line = "static int foo_bar(int a, char *b) { if (a >= 0x1F && b != 0) \
+                { return a + 3.14 * sizeof(b); } else { while (--a) b[a] = \"x\\\"y\"; } }\n";
+
+    line.repeat(5000)
+}
+
+fn bench(name: &str, src: &str, f: impl Fn(&str) -> usize) {
+    let mut total = Duration::new(0, 0);
+    let mut tokens = 0;
+    for _ in 0..ITERATIONS {
+        let begin = Instant::now();
+        tokens = f(src);
+        total += begin.elapsed();
+    }
+
+    let average = total / ITERATIONS;
+    println!(
+        "{:<12} {:>8} tokens  {:>10.2?}/iter  {:>8.2} MB/s",
+        name,
+        tokens,
+        average,
+        src.len() as f64 / average.as_secs_f64() / 1_000_000.0
+    );
+}
+
+fn main() {
+    let src = source();
+    let sequential = SequentialLexer::new();
+
+    bench("sequential", &src, |src| sequential.count(src));
+    bench("regex-set", &src, count_lexer_state);
+}
@@ -134,7 +134,7 @@ impl AbnfDefinition {
             }
         }
 
-        let token = abnf_lexer.next(src)?;
+        let token = abnf_lexer.next(src).map_err(|_| ())?;
         if token.get_type().eq(abnf_type::ABNF_TOKEN_EOF) {
             return Err(());
         }
@@ -187,7 +187,7 @@ impl AbnfDefinition {
         let mut newly_definition = true;
         let mut result: Vec<Box<AbnfDefinition>> = Vec::new();
         loop {
-            let token = abnf_lexer.next(src)?;
+            let token = abnf_lexer.next(src).map_err(|_| ())?;
 
             if token.get_type().eq(end) {
                 break;
@@ -154,7 +154,7 @@ macro_rules! set_abnf_rules {
 pub fn new_lexer_state() -> LexerState<BnfState> {
     let mut state = LexerState::new(abnf_state::ABNF_STATE_INIT, BnfState::new());
     state
-        .set_eof(|| Token::new(abnf_type::ABNF_TOKEN_EOF, ""))
+        .set_eof(|_, _| Token::new(abnf_type::ABNF_TOKEN_EOF, ""))
         .set_ignore(ABNF_IGNORE_REGEX);
 
     set_abnf_rules!(
@@ -320,6 +320,11 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_valid_rules() {
+        assert!(new_lexer_state().validate().is_ok());
+    }
+
     #[test]
     fn test_simple_abnf() {
         assert_token(
@@ -19,13 +19,13 @@ impl AbnfRules {
     pub fn parse(&mut self, src: &str) -> Result<(), ()> {
         let mut lexer_state = new_lexer_state();
         loop {
-            let rule_name_token = lexer_state.next(src)?;
+            let rule_name_token = lexer_state.next(src).map_err(|_| ())?;
             if rule_name_token.get_type().eq(abnf_type::ABNF_TOKEN_EOF) {
                 break Ok(());
             }
             let rule_name = String::from(rule_name_token.get_value());
 
-            match lexer_state.next(src)?.get_value() {
+            match lexer_state.next(src).map_err(|_| ())?.get_value() {
                 "=" => {
                     if self.rules.contains_key(&rule_name) {
                         return Err(());
@@ -0,0 +1,116 @@
+/*
+ * 字节词法解析器
+ *
+ * ByteLexerState 即源码类型为 [u8] 的 LexerState，适用于二进制协议或
+ * Latin-1 等非 UTF-8 编码的文本，通过 LexerState::new_bytes 构造。
+ * 规则使用 regex::bytes::Regex，可以在正则表达式中通过 (?-u) 匹配
+ * 任意字节，例如 (?-u)^[\x80-\xff]+。
+ *
+ * 解析流程与 LexerState 完全相同：规则的动作同样由 TokenFactory 构造，
+ * 支持匹配策略、状态栈、调试回调、恢复策略、快照以及部分源码模式等。
+ * 产生的 ByteToken 的值为源码中的字节。
+ *
+ * 行号从 1 开始，按照 \n、\r 和 \r\n 计算，列号按照字节计算。
+ * 源码开头的 EF BB BF 不会被当作 BOM 跳过。
+ *
+ */
+
+use super::LexerState;
+
+// 字节词法解析器
+pub type ByteLexerState<S, K = &'static str> = LexerState<S, K, [u8]>;
+
+#[cfg(test)]
+mod tests {
+    use super::super::{ByteToken, RecoveryPolicy, TokenFactory};
+    use super::*;
+
+    #[test]
+    fn parse_bytes() {
+        // 二进制帧：0x02 长度 数据... 0x03，帧之间是 Latin-1 编码的文本
+        let mut state = ByteLexerState::new_bytes("init", 0usize);
+        state
+            .set_eof(|_, _| ByteToken::new("eof", b""))
+            .set_ignore(r"^[ \n]+")
+            .state("init")
+            .token(r"(?-u)^[a-z\xe0-\xff]+", |_, token| {
+                TokenFactory::new("word").build(token)
+            })
+            .token(r"(?-u)^\x02[\x00-\xff]", |len, token| {
+                *len = usize::from(token[1]);
+                TokenFactory::new("begin").push_state("frame").build(token)
+            })
+            .state("frame")
+            .token(r"(?-u)^[\x00-\xff]+", |len, token| {
+                TokenFactory::new("data")
+                    .offset(*len)
+                    .pop_state(1)
+                    .build(&token[..*len])
+            });
+
+        let src = b"caf\xe9 \x02\x03\x00\xff\x03\nna\xefve";
+        for (token_type, token_value, line, column) in [
+            ("word", &b"caf\xe9"[..], 1, 0),
+            ("begin", b"\x02\x03", 1, 5),
+            ("data", b"\x00\xff\x03", 1, 7),
+            ("word", b"na\xefve", 2, 0),
+            ("eof", b"", 2, 5),
+        ] {
+            let token = state.next(src).expect("error");
+            let begin = token.get_location().get_begin();
+            assert_eq!(token_type, token.get_type());
+            assert_eq!(token_value, token.get_value());
+            assert_eq!((line, column), (begin.get_line(), begin.get_column()));
+        }
+        assert!(state.is_eof());
+
+        let token = ByteToken::new("word", b"na\xefve");
+        assert!(token.to_utf8().is_none());
+        assert_eq!("naïve", token.to_latin1().get_value());
+
+        let mut state = ByteLexerState::new_bytes("init", ());
+        state
+            .set_eof(|_, _| ByteToken::new("eof", b""))
+            .state("init")
+            .token(r"^[a-z]+", |_, token| {
+                TokenFactory::new("word").build(token)
+            });
+        let error = state.next(b"\xfe\xff").expect_err("no match");
+        assert_eq!(r"\xfe\xff", error.get_snippet());
+    }
+
+    #[test]
+    fn parse_bytes_shared() {
+        // set_ignore 替换之前的忽略字符，快照与恢复策略与 LexerState 相同
+        let mut state = ByteLexerState::new_bytes("init", ());
+        state
+            .set_eof(|_, _| ByteToken::new("eof", b""))
+            .set_ignore(r"^,")
+            .set_ignore(r"^ ")
+            .set_recovery(RecoveryPolicy::SkipChar("error"))
+            .state("init")
+            .token(r"^[a-z]+", |_, token| {
+                TokenFactory::new("word").build(token)
+            })
+            .token(r"(?-u)^\xff", |_, token| {
+                TokenFactory::new("emit").emit(
+                    token,
+                    vec![ByteToken::new("ff", token), ByteToken::new("ff", token)],
+                )
+            });
+
+        let src = b"ab \xff,cd";
+        assert_eq!(b"ab", state.next(src).expect("error").get_value());
+        let snapshot = state.dump();
+        for _ in 0..2 {
+            let types: Vec<_> = state
+                .tokens(src)
+                .map(|token| token.expect("error").get_type())
+                .collect();
+            assert_eq!(vec!["ff", "ff", "error", "word", "eof"], types);
+            assert!(state.is_eof());
+
+            state.restore(&snapshot);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use super::{OwnedToken, Token, TokenKind};
+
+// ByteLexerState 产生的 Token，值为源码中的字节
+pub type ByteToken<'src, K = &'static str> = Token<'src, K, [u8]>;
+
+pub type OwnedByteToken<K = &'static str> = ByteToken<'static, K>;
+
+impl<'src, K: TokenKind> ByteToken<'src, K> {
+    // 值为合法的 UTF-8 时转换为 Token，否则返回 None
+    pub fn to_utf8(&self) -> Option<Token<'_, K>> {
+        let mut token = Token::new(self.get_type(), std::str::from_utf8(self.get_value()).ok()?);
+        if self.has_location() {
+            token.set_location(self.get_location());
+        }
+
+        Some(token)
+    }
+
+    // 将值按照 Latin-1（ISO-8859-1）解码，每个字节对应一个字符
+    pub fn to_latin1(&self) -> OwnedToken<K> {
+        let mut token = Token::new_owned(
+            self.get_type(),
+            self.get_value()
+                .iter()
+                .map(|&byte| char::from(byte))
+                .collect::<String>(),
+        );
+        if self.has_location() {
+            token.set_location(self.get_location());
+        }
+
+        token
+    }
+}
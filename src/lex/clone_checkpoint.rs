@@ -0,0 +1,42 @@
+use super::CustomCheckpoint;
+use std::ops::{Deref, DerefMut};
+
+// 以自定义状态的副本作为检查点的包装
+//
+// CustomCheckpoint 没有为所有实现了 Clone 的类型提供实现，使得这些类型
+// 仍然可以自行实现更轻量的检查点；需要复制整个自定义状态时，以
+// CloneCheckpoint(state) 作为 LexerState 的自定义状态即可。
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CloneCheckpoint<T>(pub T);
+
+impl<T> CloneCheckpoint<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for CloneCheckpoint<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CloneCheckpoint<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: Clone> CustomCheckpoint for CloneCheckpoint<T> {
+    type Checkpoint = T;
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        self.0.clone()
+    }
+
+    fn restore(&mut self, checkpoint: &Self::Checkpoint) {
+        self.0.clone_from(checkpoint);
+    }
+}
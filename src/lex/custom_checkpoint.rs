@@ -0,0 +1,104 @@
+// 自定义状态的检查点
+//
+// LexerState::dump_checkpoint 和 LexerState::restore_checkpoint 通过检查点
+// 保存和还原自定义状态，使得回溯时 Token 构造方法对自定义状态的修改同样
+// 被撤销。自定义状态可以只保存需要还原的字段作为检查点；希望以自身的副本
+// 作为检查点时，可以使用 CloneCheckpoint 包装。
+pub trait CustomCheckpoint {
+    type Checkpoint;
+
+    fn checkpoint(&self) -> Self::Checkpoint;
+
+    fn restore(&mut self, checkpoint: &Self::Checkpoint);
+}
+
+// 没有自定义状态
+impl CustomCheckpoint for () {
+    type Checkpoint = ();
+
+    fn checkpoint(&self) -> Self::Checkpoint {}
+
+    fn restore(&mut self, _checkpoint: &Self::Checkpoint) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CloneCheckpoint, LexerState, Token, TokenFactory};
+    use super::*;
+
+    // 不能 Clone 的自定义状态，只保存括号深度
+    struct Depth {
+        depth: usize,
+        max_depth: usize,
+    }
+
+    impl CustomCheckpoint for Depth {
+        type Checkpoint = usize;
+
+        fn checkpoint(&self) -> Self::Checkpoint {
+            self.depth
+        }
+
+        fn restore(&mut self, checkpoint: &Self::Checkpoint) {
+            self.depth = *checkpoint;
+        }
+    }
+
+    #[test]
+    fn custom_checkpoint() {
+        let mut state = LexerState::new(
+            "init",
+            Depth {
+                depth: 0,
+                max_depth: 0,
+            },
+        );
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .state("init")
+            .token(r"^\(", |custom, token| {
+                custom.depth += 1;
+                custom.max_depth = custom.max_depth.max(custom.depth);
+                TokenFactory::new("open").build(token)
+            })
+            .token(r"^\)", |custom, token| {
+                custom.depth -= 1;
+                TokenFactory::new("close").build(token)
+            });
+
+        let src = "(())";
+        let mut tokens = state.tokens(src);
+        tokens.next();
+        assert_eq!(
+            "close",
+            tokens
+                .peek_nth_checkpoint(2)
+                .expect("token")
+                .expect("error")
+                .get_type()
+        );
+        assert_eq!(1, tokens.get_lexer().get_custom_state().depth);
+
+        let types: Vec<_> = tokens
+            .map(|token| token.expect("error").get_type())
+            .collect();
+        assert_eq!(vec!["open", "close", "close", "eof"], types);
+        assert_eq!(0, state.get_custom_state().depth);
+        assert_eq!(2, state.get_custom_state().max_depth);
+
+        // 以副本作为检查点
+        let mut state = LexerState::new("init", CloneCheckpoint(0usize));
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .state("init")
+            .token(r"^\(", |depth, token| {
+                **depth += 1;
+                TokenFactory::new("open").build(token)
+            });
+        let snapshot = state.dump_checkpoint();
+        state.next("(").expect("error");
+        assert_eq!(1, **state.get_custom_state());
+        state.restore_checkpoint(&snapshot);
+        assert_eq!(0, **state.get_custom_state());
+    }
+}
@@ -0,0 +1,36 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result};
+
+// 根据词法规格生成代码时的错误
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LexerGenerateError {
+    state: Option<&'static str>,
+    message: String,
+}
+
+impl LexerGenerateError {
+    // state: 出错的状态，与状态无关的错误为 None
+    // message: 错误信息
+    pub fn new(state: Option<&'static str>, message: String) -> Self {
+        LexerGenerateError { state, message }
+    }
+
+    pub fn get_state(&self) -> Option<&'static str> {
+        self.state
+    }
+
+    pub fn get_message(&self) -> &str {
+        self.message.as_str()
+    }
+}
+
+impl Display for LexerGenerateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.state {
+            Some(state) => write!(f, "state `{}`: {}", state, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for LexerGenerateError {}
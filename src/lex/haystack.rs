@@ -0,0 +1,178 @@
+use super::{LexError, PatternError};
+#[cfg(feature = "nfc")]
+use super::{Token, TokenKind};
+use regex::{bytes, Regex, RegexSet};
+use std::fmt::Debug;
+
+// 词法解析器所解析的源码，由 str 和 [u8] 实现
+//
+// LexerState 通过 Haystack 使用对应的正则表达式，并按照字节偏移量
+// 截取源码。str 的列号按照字符计算，[u8] 的列号按照字节计算。
+pub trait Haystack: ToOwned + Debug + PartialEq + Eq + 'static {
+    type Regex: Clone + Debug;
+    type RegexSet;
+    type SetMatches;
+
+    // 正则表达式是否只匹配合法的 UTF-8
+    const UTF8: bool;
+
+    // 编译规则的正则表达式，并检查其是否锚定在源码开头
+    fn compile(state: Option<&'static str>, re: &str) -> Result<Self::Regex, PatternError>;
+
+    fn regex_str(regex: &Self::Regex) -> &str;
+
+    // 正则表达式在 src 中第一个匹配的结束位置
+    fn find(regex: &Self::Regex, src: &Self) -> Option<usize>;
+
+    fn is_match(regex: &Self::Regex, src: &Self) -> bool;
+
+    // 构造失败时（如超出大小限制）返回 None
+    fn new_set<'a>(patterns: impl Iterator<Item = &'a str>) -> Option<Self::RegexSet>;
+
+    fn set_matches(set: &Self::RegexSet, src: &Self) -> Self::SetMatches;
+
+    fn set_matched(matches: &Self::SetMatches, index: usize) -> bool;
+
+    fn empty() -> &'static Self;
+
+    fn as_bytes(&self) -> &[u8];
+
+    // 截取 [begin, end) 的源码，越界或不在字符边界上时返回 None
+    fn get_range(&self, begin: usize, end: usize) -> Option<&Self>;
+
+    fn is_char_boundary(&self, index: usize) -> bool;
+
+    // 截取开头部分作为错误信息中的源码片段
+    fn snippet(&self) -> String;
+
+    // 将 Token 的值规范化为 NFC，[u8] 保持不变
+    #[cfg(feature = "nfc")]
+    fn into_nfc<K: TokenKind>(token: Token<'_, K, Self>) -> Token<'_, K, Self>;
+
+    // 截取 begin 之后的源码
+    fn get_from(&self, begin: usize) -> Option<&Self> {
+        self.get_range(begin, self.as_bytes().len())
+    }
+}
+
+impl Haystack for str {
+    type Regex = Regex;
+    type RegexSet = RegexSet;
+    type SetMatches = regex::SetMatches;
+
+    const UTF8: bool = true;
+
+    fn compile(state: Option<&'static str>, re: &str) -> Result<Self::Regex, PatternError> {
+        PatternError::compile(state, re)
+    }
+
+    fn regex_str(regex: &Self::Regex) -> &str {
+        regex.as_str()
+    }
+
+    fn find(regex: &Self::Regex, src: &Self) -> Option<usize> {
+        regex.find(src).map(|matched| matched.end())
+    }
+
+    fn is_match(regex: &Self::Regex, src: &Self) -> bool {
+        regex.is_match(src)
+    }
+
+    fn new_set<'a>(patterns: impl Iterator<Item = &'a str>) -> Option<Self::RegexSet> {
+        RegexSet::new(patterns).ok()
+    }
+
+    fn set_matches(set: &Self::RegexSet, src: &Self) -> Self::SetMatches {
+        set.matches(src)
+    }
+
+    fn set_matched(matches: &Self::SetMatches, index: usize) -> bool {
+        matches.matched(index)
+    }
+
+    fn empty() -> &'static Self {
+        ""
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+
+    fn get_range(&self, begin: usize, end: usize) -> Option<&Self> {
+        self.get(begin..end)
+    }
+
+    fn is_char_boundary(&self, index: usize) -> bool {
+        self.is_char_boundary(index)
+    }
+
+    fn snippet(&self) -> String {
+        LexError::snippet(self)
+    }
+
+    #[cfg(feature = "nfc")]
+    fn into_nfc<K: TokenKind>(token: Token<'_, K, Self>) -> Token<'_, K, Self> {
+        token.into_nfc()
+    }
+}
+
+impl Haystack for [u8] {
+    type Regex = bytes::Regex;
+    type RegexSet = bytes::RegexSet;
+    type SetMatches = bytes::SetMatches;
+
+    const UTF8: bool = false;
+
+    fn compile(state: Option<&'static str>, re: &str) -> Result<Self::Regex, PatternError> {
+        PatternError::compile_bytes(state, re)
+    }
+
+    fn regex_str(regex: &Self::Regex) -> &str {
+        regex.as_str()
+    }
+
+    fn find(regex: &Self::Regex, src: &Self) -> Option<usize> {
+        regex.find(src).map(|matched| matched.end())
+    }
+
+    fn is_match(regex: &Self::Regex, src: &Self) -> bool {
+        regex.is_match(src)
+    }
+
+    fn new_set<'a>(patterns: impl Iterator<Item = &'a str>) -> Option<Self::RegexSet> {
+        bytes::RegexSet::new(patterns).ok()
+    }
+
+    fn set_matches(set: &Self::RegexSet, src: &Self) -> Self::SetMatches {
+        set.matches(src)
+    }
+
+    fn set_matched(matches: &Self::SetMatches, index: usize) -> bool {
+        matches.matched(index)
+    }
+
+    fn empty() -> &'static Self {
+        b""
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn get_range(&self, begin: usize, end: usize) -> Option<&Self> {
+        self.get(begin..end)
+    }
+
+    fn is_char_boundary(&self, index: usize) -> bool {
+        index <= self.len()
+    }
+
+    fn snippet(&self) -> String {
+        LexError::byte_snippet(self)
+    }
+
+    #[cfg(feature = "nfc")]
+    fn into_nfc<K: TokenKind>(token: Token<'_, K, Self>) -> Token<'_, K, Self> {
+        token
+    }
+}
@@ -0,0 +1,364 @@
+/*
+ * 增量词法解析
+ *
+ * LexerState::lex_all 解析全部源码，并为每个 Token 记录解析它之前的
+ * LexerStateSnapshot。源码被修改后，LexerState::relex 根据这些快照
+ * 只重新解析受影响的部分：
+ *
+ *   1. 找到第一个结束位置不早于修改开始位置的 Token，再向前退一个
+ *      Token，从它的快照开始重新解析。多退一个 Token 是因为规则在
+ *      匹配时可能查看了 Token 之后的字符；
+ *   2. 在修改后的文本之后，每产生一个 Token 前比较词法解析器的状态与
+ *      原有 Token 的快照，偏移量（扣除修改带来的变化）、状态栈、缩进
+ *      状态以及自定义状态的检查点均相同时即重新同步；
+ *   3. 同步之后的原有 Token 不再解析，只调整它们的位置：与同步位置
+ *      位于同一行的位置调整行号、列号和偏移量，之后各行的位置只调整
+ *      行号和偏移量。
+ *
+ * 快照通过 CustomCheckpoint 保存自定义状态，重新解析时一并还原，
+ * 因此 Token 构造方法可以依赖并修改自定义状态。
+ *
+ */
+
+use super::{
+    CustomCheckpoint, LexError, LexerState, LexerStateSnapshot, OwnedToken, TextEdit, TokenKind,
+};
+
+// 带有解析前快照的 Token，C 为快照中自定义状态的检查点
+pub struct LexedToken<K = &'static str, C = ()> {
+    token: OwnedToken<K>,
+    snapshot: LexerStateSnapshot<K, C>,
+}
+
+impl<K: TokenKind, C> LexedToken<K, C> {
+    pub fn new(token: OwnedToken<K>, snapshot: LexerStateSnapshot<K, C>) -> Self {
+        LexedToken { token, snapshot }
+    }
+
+    pub fn get_token(&self) -> &OwnedToken<K> {
+        &self.token
+    }
+
+    // 解析该 Token 之前词法解析器的快照
+    pub fn get_snapshot(&self) -> &LexerStateSnapshot<K, C> {
+        &self.snapshot
+    }
+
+    pub fn into_token(self) -> OwnedToken<K> {
+        self.token
+    }
+
+    fn shift(mut self, shift: &Shift) -> Self {
+        self.token
+            .shift(shift.line, shift.lines, shift.columns, shift.offset);
+        self.snapshot.shift(
+            shift.line,
+            shift.lines,
+            shift.columns,
+            shift.offset,
+            shift.line_offset,
+        );
+
+        self
+    }
+}
+
+// 同步位置之后的 Token 的位置变化
+//
+// 与同步位置位于同一行（修改前的行号为 line）的位置需要调整列号和所在行的起始位置，
+// 之后各行的位置只需调整行号和偏移量。
+struct Shift {
+    line: usize,
+    lines: isize,
+    columns: isize,
+    offset: isize,
+    line_offset: isize,
+}
+
+impl Shift {
+    // previous: 同步位置修改前的快照
+    // current: 同步位置修改后的快照
+    fn new<K: TokenKind, C>(
+        previous: &LexerStateSnapshot<K, C>,
+        current: &LexerStateSnapshot<K, C>,
+    ) -> Self {
+        let diff = |previous: usize, current: usize| current as isize - previous as isize;
+
+        Shift {
+            line: previous.get_line(),
+            lines: diff(previous.get_line(), current.get_line()),
+            columns: diff(previous.get_column(), current.get_column()),
+            offset: diff(previous.get_offset(), current.get_offset()),
+            line_offset: diff(previous.get_line_offset(), current.get_line_offset()),
+        }
+    }
+}
+
+// 修改之后的快照 current 能否与修改之前的快照 previous 重新同步
+fn resync<K: TokenKind, C: PartialEq>(
+    previous: &LexerStateSnapshot<K, C>,
+    current: &LexerStateSnapshot<K, C>,
+    delta: isize,
+) -> bool {
+    previous.get_offset().saturating_add_signed(delta) == current.get_offset()
+        && previous.is_eof() == current.is_eof()
+        && previous.get_pending().is_empty()
+        && current.get_pending().is_empty()
+        && previous.get_indent_stack() == current.get_indent_stack()
+        && previous.get_token_snapshot().get_current_state()
+            == current.get_token_snapshot().get_current_state()
+        && previous.get_token_snapshot().get_state_stack()
+            == current.get_token_snapshot().get_state_stack()
+        && previous.get_custom_state() == current.get_custom_state()
+}
+
+impl<S, K: TokenKind> LexerState<S, K>
+where
+    S: CustomCheckpoint,
+    S::Checkpoint: PartialEq,
+{
+    // 从当前位置解析到源码结束，返回包括终止Token在内的所有Token及其快照
+    // src: 源码
+    pub fn lex_all(&mut self, src: &str) -> Result<Vec<LexedToken<K, S::Checkpoint>>, LexError> {
+        let mut tokens = Vec::new();
+        while !self.is_eof() {
+            let snapshot = self.dump_checkpoint();
+            let token = self.next(src)?.into_owned();
+            tokens.push(LexedToken::new(token, snapshot));
+        }
+
+        Ok(tokens)
+    }
+
+    // 源码修改后重新解析受影响的部分，返回修改后源码的所有Token
+    //
+    // 返回后词法解析器停在重新同步的位置；解析出错时原有的Token将被丢弃，
+    // 调用者可以重置词法解析器后重新完整解析。
+    //
+    // src: 修改后的完整源码
+    // tokens: 由 lex_all 或 relex 得到的修改前的Token，为空时回到源码开头和初始状态
+    //         完整解析（参见 rewind），自定义状态不会被还原
+    // edit: 对源码的修改
+    pub fn relex(
+        &mut self,
+        src: &str,
+        mut tokens: Vec<LexedToken<K, S::Checkpoint>>,
+        edit: &TextEdit,
+    ) -> Result<Vec<LexedToken<K, S::Checkpoint>>, LexError> {
+        let touched = tokens
+            .iter()
+            .position(|lexed| lexed.token.get_location().get_end().get_offset() >= edit.get_begin())
+            .unwrap_or(tokens.len().saturating_sub(1));
+        let mut previous = tokens.split_off(touched.saturating_sub(1)).into_iter();
+        match previous.next() {
+            Some(restart) => self.restore_checkpoint(&restart.snapshot),
+            None => {
+                self.rewind();
+
+                return self.lex_all(src);
+            }
+        }
+
+        let delta = edit.get_delta();
+        let edit_end = edit.get_begin() + edit.get_text().len();
+        let mut previous = previous.peekable();
+        while !self.is_eof() {
+            let snapshot = self.dump_checkpoint();
+            if snapshot.get_offset() >= edit_end {
+                let offset = snapshot.get_offset().saturating_add_signed(-delta);
+                while previous
+                    .next_if(|lexed| lexed.snapshot.get_offset() < offset)
+                    .is_some()
+                {}
+
+                if let Some(lexed) = previous.peek() {
+                    if resync(&lexed.snapshot, &snapshot, delta) {
+                        let shift = Shift::new(&lexed.snapshot, &snapshot);
+                        tokens.extend(previous.map(|lexed| lexed.shift(&shift)));
+
+                        return Ok(tokens);
+                    }
+                }
+            }
+
+            let token = self.next(src)?.into_owned();
+            tokens.push(LexedToken::new(token, snapshot));
+        }
+
+        Ok(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Token, TokenFactory};
+    use super::*;
+
+    // calls 记录 Token 构造方法被调用的次数，不会被还原；depth 为括号深度
+    struct Counter {
+        calls: usize,
+        depth: usize,
+    }
+
+    impl CustomCheckpoint for Counter {
+        type Checkpoint = usize;
+
+        fn checkpoint(&self) -> Self::Checkpoint {
+            self.depth
+        }
+
+        fn restore(&mut self, checkpoint: &Self::Checkpoint) {
+            self.depth = *checkpoint;
+        }
+    }
+
+    fn new_state() -> LexerState<Counter> {
+        let mut state = LexerState::new("init", Counter { calls: 0, depth: 0 });
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^\s+")
+            .state("init")
+            .token(r"^[a-z]+", |counter, token| {
+                counter.calls += 1;
+                // 括号中的名称是不同的 Token
+                let token_type = if counter.depth.eq(&0) {
+                    "name"
+                } else {
+                    "inner"
+                };
+                TokenFactory::new(token_type).build(token)
+            })
+            .token(r"^\(", |counter, token| {
+                counter.calls += 1;
+                counter.depth += 1;
+                TokenFactory::new("open").build(token)
+            })
+            .token(r"^\)", |counter, token| {
+                counter.calls += 1;
+                counter.depth = counter.depth.saturating_sub(1);
+                TokenFactory::new("close").build(token)
+            })
+            .token(r"^=", |counter, token| {
+                counter.calls += 1;
+                TokenFactory::new("eq").build(token)
+            })
+            .token(r#"^""#, |counter, token| {
+                counter.calls += 1;
+                TokenFactory::new("quote").push_state("string").build(token)
+            })
+            .state("string")
+            .token(r#"^[^"]+"#, |counter, token| {
+                counter.calls += 1;
+                TokenFactory::new("text").build(token)
+            })
+            .token(r#"^""#, |counter, token| {
+                counter.calls += 1;
+                TokenFactory::new("quote").pop_state(1).build(token)
+            });
+
+        state
+    }
+
+    fn summary(tokens: &[LexedToken<&'static str, usize>]) -> Vec<(&'static str, String, String)> {
+        tokens
+            .iter()
+            .map(|lexed| {
+                (
+                    lexed.get_token().get_type(),
+                    String::from(lexed.get_token().get_value()),
+                    lexed.get_token().get_location().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    // 增量解析的结果与完整解析相同，且调用 Token 构造方法的次数不多于完整解析
+    fn assert_relex(src: &str, edit: TextEdit) {
+        let mut state = new_state();
+        let tokens = state.lex_all(src).expect("error");
+        let edited = edit.apply(src).expect("edit");
+
+        state.get_mut_custom_state().calls = 0;
+        let relexed = state.relex(&edited, tokens, &edit).expect("error");
+        let relexed_calls = state.get_custom_state().calls;
+
+        let mut full = new_state();
+        let expected = full.lex_all(&edited).expect("error");
+        assert_eq!(summary(&expected), summary(&relexed), "{:?}", edit);
+        assert!(relexed_calls <= full.get_custom_state().calls);
+
+        for (lexed, expected) in relexed.iter().zip(expected.iter()) {
+            let (snapshot, expected) = (lexed.get_snapshot(), expected.get_snapshot());
+            assert_eq!(expected.get_offset(), snapshot.get_offset());
+            assert_eq!(expected.get_line(), snapshot.get_line());
+            assert_eq!(expected.get_line_offset(), snapshot.get_line_offset());
+            assert_eq!(expected.get_column(), snapshot.get_column());
+            assert_eq!(expected.get_custom_state(), snapshot.get_custom_state());
+        }
+    }
+
+    #[test]
+    fn relex_edits() {
+        let src = "a = \"x y\"\nb = c\nd = \"e\"\nf = g\n";
+        for edit in [
+            TextEdit::new(0, 1, "abc"),
+            TextEdit::new(12, 12, "\nh = i"),
+            TextEdit::new(11, 15, ""),
+            TextEdit::new(5, 5, "\""),
+            TextEdit::new(src.len(), src.len(), "j"),
+        ] {
+            assert_relex(src, edit);
+        }
+
+        // 连续修改时只重新解析修改所在的 Token 及其之前的一个 Token
+        let mut state = new_state();
+        let mut src = String::from(src);
+        let mut tokens = state.lex_all(&src).expect("error");
+        for (edit, calls) in [
+            (TextEdit::new(0, 1, "abc"), 1),
+            (TextEdit::new(14, 15, "de"), 2),
+        ] {
+            src = edit.apply(&src).expect("edit");
+            state.get_mut_custom_state().calls = 0;
+            tokens = state.relex(&src, tokens, &edit).expect("error");
+            assert_eq!(calls, state.get_custom_state().calls);
+        }
+        assert_eq!(
+            summary(&new_state().lex_all(&src).expect("error")),
+            summary(&tokens)
+        );
+    }
+
+    #[test]
+    fn relex_empty() {
+        // 没有原有的 Token 时从源码开头和初始状态完整解析
+        let mut state = new_state();
+        state.lex_all("a = \"b").expect("error");
+        assert_eq!("string", state.get_current_state());
+
+        let src = "c = d";
+        let tokens = state
+            .relex(src, Vec::new(), &TextEdit::new(0, 0, src))
+            .expect("error");
+        assert_eq!(
+            summary(&new_state().lex_all(src).expect("error")),
+            summary(&tokens)
+        );
+        assert_eq!("init", state.get_current_state());
+    }
+
+    #[test]
+    fn relex_custom_state() {
+        // 括号改变之后名称的 Token 类型，重新同步时需要比较自定义状态
+        let src = "a (b c) d\ne (f) g\n";
+        for edit in [
+            TextEdit::new(2, 3, ""),
+            TextEdit::new(0, 0, "("),
+            TextEdit::new(6, 7, ""),
+            TextEdit::new(10, 10, "(x"),
+            TextEdit::new(12, 13, "q"),
+        ] {
+            assert_relex(src, edit);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::{Haystack, TokenKind};
+
+// 缩进模式的设定
+//
+// 开启后每一行开头的缩进宽度将与缩进栈比较，缩进增加时产生 INDENT，
+// 减少时为每个弹出的层级产生 DEDENT，每个非空行结束时产生 NEWLINE。
+// 空行（只有空白字符或注释的行）不影响缩进。
+#[derive(Clone, Copy, Debug)]
+pub struct IndentConfig<K = &'static str> {
+    indent: K,
+    dedent: K,
+    newline: K,
+    tab_width: usize,
+    ignore_nested: bool,
+}
+
+impl<K: TokenKind> IndentConfig<K> {
+    // indent: 缩进增加时产生的 Token 类型
+    // dedent: 缩进减少时产生的 Token 类型
+    // newline: 行结束时产生的 Token 类型
+    pub fn new(indent: K, dedent: K, newline: K) -> Self {
+        IndentConfig {
+            indent,
+            dedent,
+            newline,
+            tab_width: 8,
+            ignore_nested: true,
+        }
+    }
+
+    // 设定制表符的宽度，制表符将缩进补齐到该宽度的整数倍（默认为 8）
+    // 设为 0 时缩进中的制表符将产生错误，只允许使用空格缩进
+    pub fn tab_width(&mut self, tab_width: usize) -> &mut Self {
+        self.tab_width = tab_width;
+
+        self
+    }
+
+    // 设定状态栈非空时（如处于括号内）是否忽略缩进和换行（默认忽略）
+    pub fn ignore_nested(&mut self, ignore_nested: bool) -> &mut Self {
+        self.ignore_nested = ignore_nested;
+
+        self
+    }
+
+    pub fn get_indent(&self) -> K {
+        self.indent
+    }
+
+    pub fn get_dedent(&self) -> K {
+        self.dedent
+    }
+
+    pub fn get_newline(&self) -> K {
+        self.newline
+    }
+
+    pub fn get_tab_width(&self) -> usize {
+        self.tab_width
+    }
+
+    pub fn is_ignore_nested(&self) -> bool {
+        self.ignore_nested
+    }
+
+    // 计算缩进字符的宽度，遇到非缩进字符时停止
+    // 返回缩进的字节长度和宽度，不允许使用的制表符返回 None
+    pub fn measure<H: ?Sized + Haystack>(&self, src: &H) -> Option<(usize, usize)> {
+        let src = src.as_bytes();
+        let mut width = 0;
+        for (index, &byte) in src.iter().enumerate() {
+            match byte {
+                b' ' => width += 1,
+                b'\t' if self.tab_width.ne(&0) => width += self.tab_width - width % self.tab_width,
+                b'\t' => return None,
+                _ => return Some((index, width)),
+            }
+        }
+
+        Some((src.len(), width))
+    }
+}
+
+// 缩进模式的运行状态
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndentStack {
+    widths: Vec<usize>,
+    at_line_start: bool,
+    line_has_token: bool,
+}
+
+impl IndentStack {
+    pub fn new() -> Self {
+        IndentStack {
+            widths: Vec::new(),
+            at_line_start: true,
+            line_has_token: false,
+        }
+    }
+
+    // 当前的缩进宽度
+    pub fn get_width(&self) -> usize {
+        self.widths.last().copied().unwrap_or(0)
+    }
+
+    // 缩进的层数
+    pub fn get_depth(&self) -> usize {
+        self.widths.len()
+    }
+
+    pub fn is_at_line_start(&self) -> bool {
+        self.at_line_start
+    }
+
+    pub fn has_line_token(&self) -> bool {
+        self.line_has_token
+    }
+
+    pub fn push(&mut self, width: usize) {
+        self.widths.push(width);
+    }
+
+    pub fn pop(&mut self) -> Option<usize> {
+        self.widths.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.widths.clear();
+    }
+
+    pub fn set_at_line_start(&mut self, at_line_start: bool) {
+        self.at_line_start = at_line_start;
+    }
+
+    pub fn set_line_has_token(&mut self, line_has_token: bool) {
+        self.line_has_token = line_has_token;
+    }
+}
+
+impl Default for IndentStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+// 全局的名称表
+//
+// 状态名称（StateId）、词法规格文件中的 Token 类型以及反序列化得到的
+// Token 类型共用同一个名称表，相同的名称总是得到相同的序号和
+// &'static str。运行时构造的名称在首次登记时被泄漏以获得 'static 的
+// 生命周期，每个不同的名称只会泄漏一次，因此泄漏的内存以不同名称的
+// 数量为上限。只需要查找而不应登记新名称时（如反序列化 StateId）使用
+// Interner::lookup。
+//
+// 名称一经登记就不会改变，因此每个线程缓存已经见过的名称，Token 动作中
+// 频繁使用的 push_state("...") 等只在首次遇到某个名称时获取全局的锁。
+pub struct Interner {
+    names: Vec<&'static str>,
+    ids: HashMap<&'static str, u32>,
+}
+
+thread_local! {
+    static LOCAL: RefCell<Interner> = RefCell::new(Interner::new());
+}
+
+impl Interner {
+    fn new() -> Self {
+        Interner {
+            names: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    fn get() -> MutexGuard<'static, Interner> {
+        static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+
+        INTERNER
+            .get_or_init(|| Mutex::new(Interner::new()))
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // 访问当前线程缓存的名称表，线程退出过程中无法访问时返回 None
+    fn local<T, F>(f: F) -> Option<T>
+    where
+        F: FnOnce(&mut Interner) -> Option<T>,
+    {
+        LOCAL
+            .try_with(|local| {
+                local
+                    .try_borrow_mut()
+                    .ok()
+                    .and_then(|mut local| f(&mut local))
+            })
+            .ok()
+            .flatten()
+    }
+
+    // 将全局名称表中新登记的名称复制到当前线程的缓存中
+    fn sync(global: &Interner) {
+        Self::local(|local| {
+            for name in &global.names[local.names.len()..] {
+                local.push(name);
+            }
+
+            Some(())
+        });
+    }
+
+    fn push(&mut self, name: &'static str) -> u32 {
+        let id = self.names.len() as u32;
+        self.names.push(name);
+        self.ids.insert(name, id);
+
+        id
+    }
+
+    fn insert<F>(name: &str, leak: F) -> u32
+    where
+        F: FnOnce(&str) -> &'static str,
+    {
+        if let Some(id) = Self::local(|local| local.ids.get(name).copied()) {
+            return id;
+        }
+
+        let mut interner = Self::get();
+        let id = match interner.ids.get(name) {
+            Some(id) => *id,
+            None => interner.push(leak(name)),
+        };
+        Self::sync(&interner);
+
+        id
+    }
+
+    // 登记名称并返回其序号，名称尚未登记时复制并泄漏该名称
+    pub fn intern(name: &str) -> u32 {
+        Self::insert(name, |name| Box::leak(String::from(name).into_boxed_str()))
+    }
+
+    // 登记 'static 的名称，不会复制
+    pub fn intern_static(name: &'static str) -> u32 {
+        Self::insert(name, |_| name)
+    }
+
+    // 已经登记的名称的序号，不会登记新的名称
+    pub fn lookup(name: &str) -> Option<u32> {
+        Self::local(|local| local.ids.get(name).copied()).or_else(|| {
+            let interner = Self::get();
+            Self::sync(&interner);
+
+            interner.ids.get(name).copied()
+        })
+    }
+
+    // 序号对应的名称
+    pub fn get_name(id: u32) -> &'static str {
+        Self::local(|local| local.names.get(id as usize).copied()).unwrap_or_else(|| {
+            let interner = Self::get();
+            Self::sync(&interner);
+
+            interner.names[id as usize]
+        })
+    }
+
+    // 登记名称并返回 'static 的名称
+    pub fn intern_name(name: &str) -> &'static str {
+        Self::get_name(Self::intern(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{StateId, TokenKind};
+    use super::*;
+
+    #[test]
+    fn shared_names() {
+        let name = String::from("shared_") + "name";
+        assert_eq!(None, Interner::lookup(&name));
+
+        let interned = Interner::intern_name(&name);
+        assert_eq!(Some("shared_name"), <&str>::from_name(&name));
+        assert!(std::ptr::eq(interned, <&str>::from_name(&name).unwrap()));
+        assert!(std::ptr::eq(interned, StateId::new(&name).get_name()));
+        assert_eq!(Interner::lookup(&name), Some(StateId::new(&name).get_id()));
+
+        // 未登记的 Token 类型将被拒绝，也不会被登记
+        assert_eq!(None, <&str>::from_name("unregistered_kind"));
+        assert_eq!(None, Interner::lookup("unregistered_kind"));
+
+        // 其他线程登记的名称在当前线程中可以查找到
+        let id = std::thread::spawn(|| Interner::intern("thread_name"))
+            .join()
+            .expect("thread");
+        assert_eq!(Some(id), Interner::lookup("thread_name"));
+        assert_eq!("thread_name", Interner::get_name(id));
+        assert_eq!(id, StateId::from("thread_name").get_id());
+    }
+}
@@ -0,0 +1,234 @@
+use super::{Position, StateStackError};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result};
+use std::io::ErrorKind;
+
+// 错误信息中截取的未匹配源码的最大字符数
+const SNIPPET_MAX_CHARS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LexError {
+    // 当前状态下没有任何规则可以匹配剩余的源码
+    NoMatch {
+        position: Position,
+        state: &'static str,
+        snippet: String,
+    },
+    // 源码已经结束，但没有通过 set_eof 设定终止Token
+    MissingEof {
+        position: Position,
+        state: &'static str,
+    },
+    // 偏移量落在了一个 UTF-8 字符的中间
+    InvalidUtf8Boundary {
+        position: Position,
+        state: &'static str,
+        offset: usize,
+    },
+    // 状态栈中没有足够的状态可以弹出
+    StateStackUnderflow {
+        position: Position,
+        state: &'static str,
+        times: usize,
+    },
+    // 当前状态和状态栈中都没有要弹出到的状态
+    MissingState {
+        position: Position,
+        state: &'static str,
+        target: &'static str,
+    },
+    // 缩进减少后的宽度与之前任何一层缩进都不一致
+    InconsistentDedent {
+        position: Position,
+        state: &'static str,
+        width: usize,
+    },
+    // 缩进中出现了不允许使用的制表符
+    TabIndent {
+        position: Position,
+        state: &'static str,
+    },
+    // 规则的动作产生的错误
+    Action {
+        position: Position,
+        state: &'static str,
+        message: String,
+    },
+    // 源码只读入了一部分，规则可能匹配越过已读入部分末尾的文本，
+    // 只在 LexerState::set_partial 开启时产生
+    Incomplete {
+        position: Position,
+        state: &'static str,
+    },
+    // 读取源码时发生的错误
+    Io {
+        position: Position,
+        state: &'static str,
+        kind: ErrorKind,
+        message: String,
+    },
+}
+
+impl LexError {
+    // 截取 src 的开头部分作为错误信息中的源码片段
+    pub fn snippet(src: &str) -> String {
+        let mut chars = src.chars();
+        let mut snippet: String = chars.by_ref().take(SNIPPET_MAX_CHARS).collect();
+        if chars.next().is_some() {
+            snippet.push_str("...");
+        }
+
+        snippet
+    }
+
+    // 截取二进制源码的开头部分，非 ASCII 字节以 \xNN 的形式表示
+    pub fn byte_snippet(src: &[u8]) -> String {
+        let mut snippet: String = src
+            .iter()
+            .take(SNIPPET_MAX_CHARS)
+            .map(|byte| byte.escape_ascii().to_string())
+            .collect();
+        if src.len() > SNIPPET_MAX_CHARS {
+            snippet.push_str("...");
+        }
+
+        snippet
+    }
+
+    // 由状态栈的错误构造词法错误
+    pub fn state_stack(error: StateStackError, position: Position, state: &'static str) -> Self {
+        match error {
+            StateStackError::Underflow { times } => Self::StateStackUnderflow {
+                position,
+                state,
+                times,
+            },
+            StateStackError::MissingState { target } => Self::MissingState {
+                position,
+                state,
+                target: target.get_name(),
+            },
+        }
+    }
+
+    pub fn get_position(&self) -> Position {
+        match self {
+            Self::NoMatch { position, .. }
+            | Self::MissingEof { position, .. }
+            | Self::InvalidUtf8Boundary { position, .. }
+            | Self::StateStackUnderflow { position, .. }
+            | Self::MissingState { position, .. }
+            | Self::InconsistentDedent { position, .. }
+            | Self::TabIndent { position, .. }
+            | Self::Action { position, .. }
+            | Self::Incomplete { position, .. }
+            | Self::Io { position, .. } => *position,
+        }
+    }
+
+    pub fn get_state(&self) -> &'static str {
+        match self {
+            Self::NoMatch { state, .. }
+            | Self::MissingEof { state, .. }
+            | Self::InvalidUtf8Boundary { state, .. }
+            | Self::StateStackUnderflow { state, .. }
+            | Self::MissingState { state, .. }
+            | Self::InconsistentDedent { state, .. }
+            | Self::TabIndent { state, .. }
+            | Self::Action { state, .. }
+            | Self::Incomplete { state, .. }
+            | Self::Io { state, .. } => state,
+        }
+    }
+
+    pub fn get_snippet(&self) -> &str {
+        match self {
+            Self::NoMatch { snippet, .. } => snippet.as_str(),
+            _ => "",
+        }
+    }
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::NoMatch {
+                position,
+                state,
+                snippet,
+            } => write!(
+                f,
+                "no rule matched in state `{}` at {}: {:?}",
+                state, position, snippet
+            ),
+            Self::MissingEof { position, state } => write!(
+                f,
+                "reached end of input in state `{}` at {} without an eof token",
+                state, position
+            ),
+            Self::InvalidUtf8Boundary {
+                position,
+                state,
+                offset,
+            } => write!(
+                f,
+                "offset {} is not on a UTF-8 character boundary in state `{}` at {}",
+                offset, state, position
+            ),
+            Self::StateStackUnderflow {
+                position,
+                state,
+                times,
+            } => write!(
+                f,
+                "cannot pop {} state(s) from state `{}` at {}",
+                times, state, position
+            ),
+            Self::MissingState {
+                position,
+                state,
+                target,
+            } => write!(
+                f,
+                "cannot pop to state `{}` from state `{}` at {}",
+                target, state, position
+            ),
+            Self::InconsistentDedent {
+                position,
+                state,
+                width,
+            } => write!(
+                f,
+                "dedent to width {} does not match any outer indentation in state `{}` at {}",
+                width, state, position
+            ),
+            Self::TabIndent { position, state } => write!(
+                f,
+                "tab is not allowed in indentation in state `{}` at {}",
+                state, position
+            ),
+            Self::Action {
+                position,
+                state,
+                message,
+            } => write!(f, "{} in state `{}` at {}", message, state, position),
+            Self::Incomplete { position, state } => write!(
+                f,
+                "input may continue past the end of the buffer in state `{}` at {}",
+                state, position
+            ),
+            Self::Io {
+                position,
+                state,
+                message,
+                ..
+            } => write!(
+                f,
+                "failed to read input in state `{}` at {}: {}",
+                state, position, message
+            ),
+        }
+    }
+}
+
+impl Error for LexError {}
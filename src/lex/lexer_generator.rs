@@ -0,0 +1,678 @@
+/*
+ * 词法解析器代码生成
+ *
+ * LexerGenerator 将 LexerSpec 转换为一个独立的 Rust 模块，可以在
+ * build.rs 中写入 OUT_DIR 后通过 include! 引入。生成的模块包括：
+ *
+ *   - 由 token_kinds! 定义的 Token 类型枚举；
+ *   - 每个状态的规则以及忽略字符和注释预先构造成的 DFA 转移表；
+ *   - 每个状态的规则表，记录规则产生的 Token 类型和之后的状态改变；
+ *   - 由上述表格驱动的词法解析器 Lexer。
+ *
+ * Lexer 不依赖 LexerState 和 regex-automata，也不需要在运行时编译任何
+ * 正则表达式，状态以序号表示，规则按照序号查表。产生的 Token、Location 以及
+ * 无法匹配时的 LexError 与 LexerSpec::build 构造的词法解析器一致。
+ *
+ * 生成代码需要开启 generator 特性，使用生成的模块则不需要。
+ *
+ */
+
+use super::{LexerGenerateError, LexerSpec, NextStateChange, SpecRule, StateId};
+use regex_automata::dfa::{dense, Automaton, StartKind};
+use regex_automata::util::primitives::{PatternID, StateID};
+use regex_automata::util::start;
+use regex_automata::Anchored;
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+
+const DEFAULT_CRATE_PATH: &str = "hoya";
+const DEFAULT_KIND_NAME: &str = "TokenType";
+
+// 输出转移表时每行的数字个数
+const NUMBERS_PER_LINE: usize = 16;
+
+// 生成的词法解析器的驱动部分，与具体的词法规格无关
+//
+// __krate__、__Kind__ 和 __eof__ 分别替换为本库的路径、Token 类型枚举的
+// 名称和终止Token 的枚举成员。状态表 STATES、规则表 RULES 以及各状态的
+// DFA 表 DFAS 和忽略字符的 DFA 表 IGNORE 由 LexerGenerator::generate 根据
+// 词法规格生成。
+const DRIVER: &str = r#"// DFA 的转移表
+//
+// 0 号状态为死状态。starts 的第一个元素为匹配所有模式的起始状态，之后依次为
+// 只匹配各个模式的起始状态。matches 为 0 表示不是匹配状态，否则为匹配的模式
+// 序号加 1，匹配状态比匹配的文本晚一个字节（或源码末尾 eoi）到达。
+struct Table {
+    classes: [u8; 256],
+    class_count: usize,
+    starts: &'static [u32],
+    transitions: &'static [u32],
+    eoi: &'static [u32],
+    matches: &'static [u32],
+}
+
+// 状态改变，状态以其在 STATES 中的序号表示
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+enum Change {
+    Push(usize),
+    Pop(usize),
+    Replace(usize),
+    PopTo(usize),
+    Reset,
+    Begin(usize),
+}
+
+// 规则产生的 Token 类型（None 表示跳过匹配的文本）和之后的状态改变
+struct Rule {
+    kind: Option<__Kind__>,
+    changes: &'static [Change],
+}
+
+// 从第 start 个起始状态开始在 src 开头运行 DFA，返回最后一个匹配的模式序号和匹配长度
+fn search(table: &Table, start: usize, src: &str) -> Option<(usize, usize)> {
+    let mut state = table.starts[start] as usize;
+    let mut matched = None;
+    for (offset, byte) in src.bytes().enumerate() {
+        let class = table.classes[usize::from(byte)] as usize;
+        state = table.transitions[state * table.class_count + class] as usize;
+        if state == 0 {
+            return matched;
+        }
+        if table.matches[state] != 0 {
+            matched = Some((table.matches[state] as usize - 1, offset));
+        }
+    }
+
+    state = table.eoi[state] as usize;
+    if table.matches[state] != 0 {
+        matched = Some((table.matches[state] as usize - 1, src.len()));
+    }
+
+    matched
+}
+
+// 在 src 开头运行 DFA，返回第一个匹配非空串的模式序号和匹配长度
+fn find(table: &Table, src: &str) -> Option<(usize, usize)> {
+    let non_empty = |start| search(table, start, src).filter(|(_, len)| *len != 0);
+
+    // 从匹配所有模式的起始状态开始时，DFA 的结果即为第一个能够匹配的模式，
+    // 匹配到空串时需要逐个尝试之后的模式
+    non_empty(0).or_else(|| (1..table.starts.len()).find_map(non_empty))
+}
+
+// 由 DFA 表和规则表驱动的词法解析器
+pub struct Lexer {
+    state: usize,
+    state_stack: Vec<usize>,
+    offset: usize,
+    line: usize,
+    column: usize,
+    is_eof: bool,
+}
+
+impl Default for Lexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[allow(dead_code)]
+impl Lexer {
+    pub fn new() -> Self {
+        Lexer {
+            state: 0,
+            state_stack: Vec::new(),
+            offset: 0,
+            line: 1,
+            column: 0,
+            is_eof: false,
+        }
+    }
+
+    pub fn get_current_state(&self) -> &'static str {
+        STATES[self.state]
+    }
+
+    // 当前文本指针所在的位置
+    pub fn get_current_position(&self) -> __krate__::Position {
+        __krate__::Position::new(self.line, self.column, self.offset)
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.is_eof
+    }
+
+    // 以迭代器的方式获取 Token，迭代在产生终止Token或者发生错误后结束
+    pub fn tokens<'a, 's: 'a>(
+        &'a mut self,
+        src: &'s str,
+    ) -> impl Iterator<Item = Result<__krate__::Token<'s, __Kind__>, __krate__::LexError>> + 'a {
+        let mut finished = false;
+
+        std::iter::from_fn(move || {
+            if finished {
+                return None;
+            }
+
+            let result = self.next(src);
+            finished = result.is_err() || self.is_eof;
+
+            Some(result)
+        })
+    }
+
+    // 从源码中获取一个Token
+    pub fn next<'s>(
+        &mut self,
+        src: &'s str,
+    ) -> Result<__krate__::Token<'s, __Kind__>, __krate__::LexError> {
+        loop {
+            if self.is_eof {
+                break Ok(self.next_eof());
+            }
+
+            self.skip_bom(src);
+            if self.offset >= src.len() || self.skip_ignore(src) {
+                break Ok(self.next_eof());
+            }
+
+            let rest = &src[self.offset..];
+            let begin = self.get_current_position();
+            let (index, len) = match DFAS[self.state].and_then(|table| find(table, rest)) {
+                Some(matched) => matched,
+                None => {
+                    break Err(__krate__::LexError::NoMatch {
+                        position: begin,
+                        state: STATES[self.state],
+                        snippet: __krate__::LexError::snippet(rest),
+                    })
+                }
+            };
+
+            let rule = &RULES[self.state][index];
+            rule.changes
+                .iter()
+                .for_each(|change| self.change_state(*change));
+            self.advance(src, len);
+
+            if let Some(kind) = rule.kind {
+                let mut token = __krate__::Token::new(kind, &rest[..len]);
+                token.set_location(__krate__::Location::new(begin, self.get_current_position()));
+
+                break Ok(token);
+            }
+        }
+    }
+
+    fn next_eof(&mut self) -> __krate__::Token<'static, __Kind__> {
+        self.is_eof = true;
+
+        let position = self.get_current_position();
+        let mut token = __krate__::Token::new(__Kind__::__eof__, "");
+        token.set_location(__krate__::Location::new(position, position));
+
+        token
+    }
+
+    // 在源码开头跳过 UTF-8 BOM
+    fn skip_bom(&mut self, src: &str) {
+        if self.offset == 0 && src.starts_with('\u{feff}') {
+            self.offset = '\u{feff}'.len_utf8();
+        }
+    }
+
+    // 跳过忽略字符和注释，返回是否已到达源码末尾
+    fn skip_ignore(&mut self, src: &str) -> bool {
+        while let Some((_, len)) = IGNORE.and_then(|table| find(table, &src[self.offset..])) {
+            self.advance(src, len);
+        }
+
+        self.offset >= src.len()
+    }
+
+    // 改变当前状态，状态栈中没有足够的状态时与 LexerState 一样忽略
+    fn change_state(&mut self, change: Change) {
+        match change {
+            Change::Push(state) => {
+                self.state_stack.push(self.state);
+                self.state = state;
+            }
+            Change::Pop(times) => {
+                for _ in 0..times {
+                    match self.state_stack.pop() {
+                        Some(state) => self.state = state,
+                        None => break,
+                    }
+                }
+            }
+            Change::Replace(state) => self.state = state,
+            Change::PopTo(state) => {
+                if self.state != state {
+                    if let Some(index) = self
+                        .state_stack
+                        .iter()
+                        .rposition(|stacked| *stacked == state)
+                    {
+                        self.state_stack.truncate(index);
+                        self.state = state;
+                    }
+                }
+            }
+            Change::Reset => {
+                self.state_stack.clear();
+                self.state = 0;
+            }
+            Change::Begin(state) => {
+                self.state_stack.clear();
+                self.state = state;
+            }
+        }
+    }
+
+    // 向前移动文本指针，并更新行号和列号，列号按照字符计算
+    fn advance(&mut self, src: &str, len: usize) {
+        let begin = self.offset;
+        self.offset += len;
+
+        // \r\n 在遇到 \r 时已经计为一行，其后的 \n 不再重复计算
+        let mut after_cr = src[..begin].ends_with('\r');
+        for c in src[begin..self.offset].chars() {
+            match c {
+                '\n' if after_cr => {}
+                '\n' | '\r' => {
+                    self.line += 1;
+                    self.column = 0;
+                }
+                _ => self.column += 1,
+            }
+            after_cr = c == '\r';
+        }
+    }
+}
+"#;
+
+pub struct LexerGenerator<'a> {
+    spec: &'a LexerSpec,
+    crate_path: String,
+    kind_name: String,
+}
+
+// 将 Token 类型的名称转换为枚举成员的名称，如 left_paren 转换为 LeftParen
+fn variant_name(kind: &str) -> String {
+    let mut name = String::new();
+    for word in kind.split('_').filter(|word| !word.is_empty()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+
+    if name.eq("Self") {
+        name.push('_');
+    }
+
+    name
+}
+
+// 输出一组数字，每行 NUMBERS_PER_LINE 个
+fn write_numbers<T: Display>(code: &mut String, numbers: &[T]) {
+    for line in numbers.chunks(NUMBERS_PER_LINE) {
+        code.push_str("       ");
+        for number in line {
+            let _ = write!(code, " {},", number);
+        }
+        code.push('\n');
+    }
+}
+
+// 由 regex-automata 构造的 DFA 转换而成的转移表，对应生成代码中的 Table
+//
+// 状态按照从各个起始状态出发、依次经过字节 0 到 255 和源码末尾的广度优先
+// 顺序编号，0 号状态为死状态；转移完全相同的字节归为同一个字节类，按照首次
+// 出现的顺序编号。同一组模式的最小 DFA 在这样的编号下是唯一的，因此生成的
+// 表格不随 regex-automata 的版本或序列化格式变化。
+struct DfaTable {
+    classes: Vec<u8>,
+    class_count: usize,
+    starts: Vec<u32>,
+    transitions: Vec<u32>,
+    eoi: Vec<u32>,
+    matches: Vec<u32>,
+}
+
+impl DfaTable {
+    // 状态在转移表中的编号，首次遇到的状态加入 order
+    fn number(
+        dfa: &dense::DFA<Vec<u32>>,
+        state: StateID,
+        order: &mut Vec<StateID>,
+        numbers: &mut HashMap<StateID, u32>,
+    ) -> Result<u32, String> {
+        if dfa.is_dead_state(state) {
+            return Ok(0);
+        }
+        if dfa.is_quit_state(state) {
+            return Err(String::from("the DFA may quit before finishing a search"));
+        }
+
+        Ok(*numbers.entry(state).or_insert_with(|| {
+            order.push(state);
+            order.len() as u32
+        }))
+    }
+
+    fn new(dfa: &dense::DFA<Vec<u32>>) -> Result<Self, String> {
+        let mut order = Vec::new();
+        let mut numbers = HashMap::new();
+
+        let mut anchors = vec![Anchored::Yes];
+        for pattern in 0..dfa.pattern_len() {
+            let pattern = PatternID::new(pattern).map_err(|error| error.to_string())?;
+            anchors.push(Anchored::Pattern(pattern));
+        }
+        let mut starts = Vec::with_capacity(anchors.len());
+        for anchored in anchors {
+            let start = dfa
+                .start_state(&start::Config::new().anchored(anchored))
+                .map_err(|error| error.to_string())?;
+            starts.push(Self::number(dfa, start, &mut order, &mut numbers)?);
+        }
+
+        // 死状态的所有转移都回到自身
+        let mut rows = vec![vec![0; 256]];
+        let mut eoi = vec![0];
+        let mut matches = vec![0];
+        let mut next = 0;
+        while let Some(state) = order.get(next).copied() {
+            next += 1;
+
+            let mut row = Vec::with_capacity(256);
+            for byte in 0..=u8::MAX {
+                let to = dfa.next_state(state, byte);
+                row.push(Self::number(dfa, to, &mut order, &mut numbers)?);
+            }
+            rows.push(row);
+            let to = dfa.next_eoi_state(state);
+            eoi.push(Self::number(dfa, to, &mut order, &mut numbers)?);
+            matches.push(match dfa.is_match_state(state) {
+                true => dfa.match_pattern(state, 0).as_u32() + 1,
+                false => 0,
+            });
+        }
+
+        let mut columns: Vec<Vec<u32>> = Vec::new();
+        let mut classes = Vec::with_capacity(256);
+        for byte in 0..256 {
+            let column: Vec<u32> = rows.iter().map(|row| row[byte]).collect();
+            let class = match columns.iter().position(|other| other.eq(&column)) {
+                Some(class) => class,
+                None => {
+                    columns.push(column);
+                    columns.len() - 1
+                }
+            };
+            classes.push(class as u8);
+        }
+
+        let transitions = (0..rows.len())
+            .flat_map(|state| columns.iter().map(move |column| column[state]))
+            .collect();
+
+        Ok(DfaTable {
+            classes,
+            class_count: columns.len(),
+            starts,
+            transitions,
+            eoi,
+            matches,
+        })
+    }
+
+    // 生成 Table 类型的静态变量
+    fn write(&self, code: &mut String, name: &str, comment: &str) {
+        let _ = writeln!(code, "\n// {}", comment);
+        let _ = writeln!(code, "static {}: Table = Table {{", name);
+        code.push_str("    classes: [\n");
+        write_numbers(code, &self.classes);
+        let _ = writeln!(code, "    ],\n    class_count: {},", self.class_count);
+        for (field, numbers) in [
+            ("starts", &self.starts),
+            ("transitions", &self.transitions),
+            ("eoi", &self.eoi),
+            ("matches", &self.matches),
+        ] {
+            let _ = writeln!(code, "    {}: &[", field);
+            write_numbers(code, numbers);
+            code.push_str("    ],\n");
+        }
+        code.push_str("};\n");
+    }
+}
+
+impl<'a> LexerGenerator<'a> {
+    pub fn new(spec: &'a LexerSpec) -> Self {
+        LexerGenerator {
+            spec,
+            crate_path: String::from(DEFAULT_CRATE_PATH),
+            kind_name: String::from(DEFAULT_KIND_NAME),
+        }
+    }
+
+    // 设定生成的代码中引用本库时使用的路径（默认为 hoya）
+    pub fn crate_path(&mut self, crate_path: &str) -> &mut Self {
+        self.crate_path = String::from(crate_path);
+
+        self
+    }
+
+    // 设定生成的 Token 类型枚举的名称（默认为 TokenType）
+    pub fn kind_name(&mut self, kind_name: &str) -> &mut Self {
+        self.kind_name = String::from(kind_name);
+
+        self
+    }
+
+    // 所有 Token 类型的名称和对应的枚举成员，按照首次出现的顺序排列
+    fn kinds(&self) -> Result<Vec<(&'static str, String)>, LexerGenerateError> {
+        let mut kinds: Vec<(&'static str, String)> = Vec::new();
+        let names = self
+            .spec
+            .get_rules()
+            .iter()
+            .filter_map(SpecRule::get_kind)
+            .chain(Some(self.spec.get_eof()));
+
+        for name in names {
+            if kinds.iter().any(|(kind, _)| kind.eq(&name)) {
+                continue;
+            }
+
+            let variant = variant_name(name);
+            if let Some((kind, _)) = kinds.iter().find(|(_, other)| other.eq(&variant)) {
+                return Err(LexerGenerateError::new(
+                    None,
+                    format!(
+                        "token types `{}` and `{}` map to the same variant `{}`",
+                        kind, name, variant
+                    ),
+                ));
+            }
+
+            kinds.push((name, variant));
+        }
+
+        Ok(kinds)
+    }
+
+    // 构造 DFA 并转换为转移表
+    // state: 规则所属的状态，忽略字符和注释为 None
+    fn dfa(
+        &self,
+        state: Option<StateId>,
+        patterns: &[&str],
+    ) -> Result<DfaTable, LexerGenerateError> {
+        dense::Builder::new()
+            .configure(
+                dense::Config::new()
+                    .start_kind(StartKind::Anchored)
+                    .starts_for_each_pattern(true)
+                    .minimize(true),
+            )
+            .build_many(patterns)
+            .map_err(|error| error.to_string())
+            .and_then(|dfa| DfaTable::new(&dfa))
+            .map_err(|message| {
+                LexerGenerateError::new(state.map(|state| state.get_name()), message)
+            })
+    }
+
+    // 状态在生成的状态表中的序号
+    fn state_index(&self, state: StateId) -> Result<usize, LexerGenerateError> {
+        self.spec
+            .get_states()
+            .iter()
+            .position(|declared| declared.eq(&state))
+            .ok_or_else(|| LexerGenerateError::new(None, format!("undeclared state `{}`", state)))
+    }
+
+    // 生成规则的状态改变
+    fn changes(&self, rule: &SpecRule) -> Result<String, LexerGenerateError> {
+        let mut changes = Vec::new();
+        for change in rule.get_next_state() {
+            changes.push(match change {
+                NextStateChange::Push(state) => {
+                    format!("Change::Push({})", self.state_index(*state)?)
+                }
+                NextStateChange::Pop(times) => format!("Change::Pop({})", times),
+                NextStateChange::Replace(state) => {
+                    format!("Change::Replace({})", self.state_index(*state)?)
+                }
+                NextStateChange::PopTo(state) => {
+                    format!("Change::PopTo({})", self.state_index(*state)?)
+                }
+                NextStateChange::Reset => String::from("Change::Reset"),
+                NextStateChange::Begin(state) => {
+                    format!("Change::Begin({})", self.state_index(*state)?)
+                }
+            });
+        }
+
+        Ok(changes.join(", "))
+    }
+
+    // 生成词法解析器模块的源码
+    pub fn generate(&self) -> Result<String, LexerGenerateError> {
+        let krate = self.crate_path.as_str();
+        let kind = self.kind_name.as_str();
+        let kinds = self.kinds()?;
+        let variant = |name: &str| {
+            kinds
+                .iter()
+                .find(|(kind, _)| kind.eq(&name))
+                .map(|(_, variant)| variant.as_str())
+                .unwrap_or_default()
+        };
+
+        let mut code = String::new();
+        code.push_str("// 由 hoya 根据词法规格生成，请勿手动修改\n\n");
+
+        let _ = writeln!(code, "{}::token_kinds! {{", krate);
+        let _ = writeln!(code, "    pub enum {} {{", kind);
+        for (name, variant) in kinds.iter() {
+            let _ = writeln!(code, "        {} => {:?},", variant, name);
+        }
+        code.push_str("    }\n}\n\n");
+
+        code.push_str(
+            &DRIVER
+                .replace("__krate__", krate)
+                .replace("__Kind__", kind)
+                .replace("__eof__", variant(self.spec.get_eof())),
+        );
+
+        let states = self.spec.get_states();
+        let _ = writeln!(code, "\nstatic STATES: [&str; {}] = [", states.len());
+        for state in states {
+            let _ = writeln!(code, "    {:?},", state.get_name());
+        }
+        code.push_str("];\n");
+
+        let mut dfas = Vec::new();
+        let mut tables = Vec::new();
+        for (index, state) in states.iter().enumerate() {
+            let rules: Vec<&SpecRule> = self
+                .spec
+                .get_rules()
+                .iter()
+                .filter(|rule| rule.get_states().contains(state))
+                .collect();
+            if rules.is_empty() {
+                dfas.push(String::from("None"));
+                tables.push(String::from("    &[],\n"));
+                continue;
+            }
+
+            let patterns: Vec<&str> = rules.iter().map(|rule| rule.get_pattern()).collect();
+            let name = format!("DFA_{}", index);
+            self.dfa(Some(*state), &patterns)?.write(
+                &mut code,
+                &name,
+                &format!("状态 {} 的 DFA", state),
+            );
+            dfas.push(format!("Some(&{})", name));
+
+            let mut table = format!("    // {}\n    &[\n", state);
+            for rule in rules {
+                let _ = writeln!(
+                    table,
+                    "        // {}: {}",
+                    rule.get_line(),
+                    rule.get_pattern()
+                );
+                let kind = match rule.get_kind() {
+                    Some(name) => format!("Some({}::{})", kind, variant(name)),
+                    None => String::from("None"),
+                };
+                let _ = writeln!(
+                    table,
+                    "        Rule {{ kind: {}, changes: &[{}] }},",
+                    kind,
+                    self.changes(rule)?
+                );
+            }
+            table.push_str("    ],\n");
+            tables.push(table);
+        }
+
+        let ignore: Vec<&str> = self
+            .spec
+            .get_ignore()
+            .into_iter()
+            .chain(self.spec.get_comments().iter().map(String::as_str))
+            .collect();
+        let ignore = if ignore.is_empty() {
+            String::from("None")
+        } else {
+            self.dfa(None, &ignore)?
+                .write(&mut code, "DFA_IGNORE", "忽略字符和注释的 DFA");
+
+            String::from("Some(&DFA_IGNORE)")
+        };
+
+        let _ = writeln!(code, "\nstatic RULES: [&[Rule]; {}] = [", states.len());
+        tables.iter().for_each(|table| code.push_str(table));
+        code.push_str("];\n");
+
+        let _ = writeln!(
+            code,
+            "\nstatic DFAS: [Option<&Table>; {}] = [{}];",
+            states.len(),
+            dfas.join(", ")
+        );
+        let _ = writeln!(code, "\nstatic IGNORE: Option<&Table> = {};", ignore);
+
+        Ok(code)
+    }
+}
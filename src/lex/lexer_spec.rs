@@ -0,0 +1,634 @@
+/*
+ * 词法规格文件
+ *
+ * 以类似 lex 的文本格式描述词法解析器，解析后构造 LexerState。
+ *
+ *   # 定义部分：命名定义、状态、忽略字符、注释和终止Token
+ *   DIGIT    [0-9]
+ *   %states  INITIAL STRING
+ *   %ignore  [ \t\r\n]+
+ *   %comment //[^\n]*
+ *   %eof     eof
+ *   %%
+ *   # 规则部分：<状态>模式 Token类型 状态变化
+ *   {DIGIT}+         number
+ *   <INITIAL>"\""    quote push STRING
+ *   <STRING>[^"]+    text
+ *   <STRING>"\""     quote pop
+ *   <*>"@"           skip
+ *
+ * 模式以空白字符结束（字符类和双引号中的空白除外），双引号中的内容
+ * 按字面匹配并视为一个整体，可以与其他部分拼接，如 "a"+ 或 ab"c d"，
+ * {NAME} 引用之前的命名定义，定义部分的模式之后不能有其他文本。模式会自动锚定在未解析
+ * 源码的开头。没有指定状态的规则属于初始状态，<*> 表示所有状态。
+ * Token类型为 skip 时跳过匹配的文本；状态变化与 TokenFactory 一致，
+ * 包括 push S、pop [N]、replace S、pop_to S、reset 和 begin S。
+ * 以 # 开头的行为注释，以 # 开头的模式需要写作 \#。
+ *
+ */
+
+use super::{
+    Interner, LexerSpecError, LexerState, NextStateChange, PatternError, StateChange, StateId,
+    Token, TokenAction,
+};
+use std::collections::HashMap;
+
+const DEFAULT_STATE: &str = "INITIAL";
+const DEFAULT_EOF: &str = "eof";
+const SKIP_KIND: &str = "skip";
+
+// 词法规格中的一条规则
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpecRule {
+    line: usize,
+    states: Vec<StateId>,
+    pattern: String,
+    kind: Option<&'static str>,
+    next_state: Vec<NextStateChange>,
+}
+
+impl SpecRule {
+    // 规则所在的行号
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    pub fn get_states(&self) -> &[StateId] {
+        self.states.as_slice()
+    }
+
+    // 展开定义并锚定后的正则表达式
+    pub fn get_pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    // Token 类型，跳过匹配文本的规则为 None
+    pub fn get_kind(&self) -> Option<&'static str> {
+        self.kind
+    }
+
+    pub fn get_next_state(&self) -> &[NextStateChange] {
+        self.next_state.as_slice()
+    }
+}
+
+// 解析后的词法规格
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LexerSpec {
+    states: Vec<StateId>,
+    ignore: Option<String>,
+    comments: Vec<String>,
+    eof: &'static str,
+    rules: Vec<SpecRule>,
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c.eq(&'_'))
+        && chars.all(|c| c.is_ascii_alphanumeric() || c.eq(&'_'))
+}
+
+// 逐行解析规格文件时的状态
+struct SpecParser {
+    line: usize,
+    definitions: HashMap<String, String>,
+    declared: bool,
+    spec: LexerSpec,
+}
+
+impl SpecParser {
+    fn error<T>(&self, message: String) -> Result<T, LexerSpecError> {
+        Err(LexerSpecError::new(self.line, message))
+    }
+
+    // 读取一个模式，返回展开定义后的正则表达式和剩余的文本
+    fn pattern<'a>(&self, src: &'a str) -> Result<(String, &'a str), LexerSpecError> {
+        let mut chars = src.char_indices().peekable();
+        let mut pattern = String::new();
+        let mut in_class = false;
+        while let Some((index, c)) = chars.next() {
+            match c {
+                // 双引号中的内容按字面匹配，结束后继续读取模式的其余部分
+                '"' if !in_class => {
+                    let mut literal = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '"')) => break,
+                            Some((_, '\\')) => {
+                                if let Some((_, escaped)) = chars.next() {
+                                    literal.push(escaped);
+                                }
+                            }
+                            Some((_, c)) => literal.push(c),
+                            None => return self.error(String::from("unterminated string literal")),
+                        }
+                    }
+
+                    pattern.push_str("(?:");
+                    pattern.push_str(&regex::escape(&literal));
+                    pattern.push(')');
+                }
+                '\\' => {
+                    pattern.push(c);
+                    if let Some((_, escaped)) = chars.next() {
+                        pattern.push(escaped);
+                    }
+                }
+                '[' if !in_class => {
+                    in_class = true;
+                    pattern.push(c);
+                    // 字符类开头的 ] 和 ^] 按字面匹配
+                    if chars.peek().is_some_and(|(_, c)| c.eq(&'^')) {
+                        pattern.push('^');
+                        chars.next();
+                    }
+                    if chars.peek().is_some_and(|(_, c)| c.eq(&']')) {
+                        pattern.push(']');
+                        chars.next();
+                    }
+                }
+                ']' if in_class => {
+                    in_class = false;
+                    pattern.push(c);
+                }
+                '{' if !in_class => {
+                    let rest = &src[index + 1..];
+                    let name = rest
+                        .find('}')
+                        .map(|end| &rest[..end])
+                        .filter(|name| is_name(name));
+                    match name {
+                        Some(name) => match self.definitions.get(name) {
+                            Some(definition) => {
+                                pattern.push_str("(?:");
+                                pattern.push_str(definition);
+                                pattern.push(')');
+                                (0..=name.len()).for_each(|_| {
+                                    chars.next();
+                                });
+                            }
+                            None => return self.error(format!("undefined definition `{}`", name)),
+                        },
+                        None => pattern.push(c),
+                    }
+                }
+                _ if c.is_whitespace() && !in_class => return Ok((pattern, &src[index..])),
+                _ => pattern.push(c),
+            }
+        }
+
+        if in_class {
+            self.error(String::from("unterminated character class"))
+        } else {
+            Ok((pattern, ""))
+        }
+    }
+
+    // 读取一个锚定在源码开头的模式，并检查其能否编译
+    fn anchored_pattern<'a>(
+        &self,
+        state: Option<&'static str>,
+        src: &'a str,
+    ) -> Result<(String, &'a str), LexerSpecError> {
+        let (pattern, rest) = self.pattern(src)?;
+        if pattern.is_empty() {
+            return self.error(String::from("missing pattern"));
+        }
+
+        let pattern = format!("^(?:{})", pattern);
+        match PatternError::compile(state, &pattern) {
+            Ok(_) => Ok((pattern, rest)),
+            Err(error) => self.error(error.to_string()),
+        }
+    }
+
+    // 模式之后不能有其他文本
+    fn end(&self, rest: &str) -> Result<(), LexerSpecError> {
+        match rest.trim() {
+            "" => Ok(()),
+            rest => self.error(format!("unexpected `{}` after pattern", rest)),
+        }
+    }
+
+    fn state(&self, name: &str) -> Result<StateId, LexerSpecError> {
+        let state = StateId::new(name);
+
+        if self.spec.states.contains(&state) {
+            Ok(state)
+        } else {
+            self.error(format!("undeclared state `{}`", name))
+        }
+    }
+
+    fn definition(&mut self, src: &str) -> Result<(), LexerSpecError> {
+        let (name, rest) = src.split_at(src.find(char::is_whitespace).unwrap_or(src.len()));
+        let rest = rest.trim();
+
+        match name {
+            "%states" => {
+                if self.declared {
+                    return self.error(String::from("states are already declared"));
+                }
+
+                let states: Vec<_> = rest.split_whitespace().collect();
+                if let Some(state) = states.iter().find(|state| !is_name(state)) {
+                    return self.error(format!("invalid state name `{}`", state));
+                }
+                if states.is_empty() {
+                    return self.error(String::from("missing state names"));
+                }
+
+                self.declared = true;
+                self.spec.states = states.into_iter().map(StateId::new).collect();
+            }
+            "%ignore" => {
+                let (pattern, rest) = self.anchored_pattern(None, rest)?;
+                self.end(rest)?;
+                self.spec.ignore = Some(pattern);
+            }
+            "%comment" => {
+                let (pattern, rest) = self.anchored_pattern(None, rest)?;
+                self.end(rest)?;
+                self.spec.comments.push(pattern);
+            }
+            "%eof" => {
+                if !is_name(rest) {
+                    return self.error(format!("invalid eof token type `{}`", rest));
+                }
+
+                self.spec.eof = Interner::intern_name(rest);
+            }
+            _ if name.starts_with('%') => {
+                return self.error(format!("unknown directive `{}`", name));
+            }
+            _ if is_name(name) => {
+                let (pattern, rest) = self.pattern(rest)?;
+                if pattern.is_empty() {
+                    return self.error(format!("missing pattern for definition `{}`", name));
+                }
+                self.end(rest)?;
+                if self
+                    .definitions
+                    .insert(String::from(name), pattern)
+                    .is_some()
+                {
+                    return self.error(format!("duplicate definition `{}`", name));
+                }
+            }
+            _ => return self.error(format!("invalid definition name `{}`", name)),
+        }
+
+        Ok(())
+    }
+
+    fn rule(&mut self, src: &str) -> Result<(), LexerSpecError> {
+        let mut src = src;
+        let mut states = vec![self.spec.states[0]];
+        if src.starts_with('<') {
+            let end = match src.find('>') {
+                Some(end) => end,
+                None => return self.error(String::from("unterminated start condition")),
+            };
+
+            states = match &src[1..end] {
+                "*" => self.spec.states.clone(),
+                names => names
+                    .split(',')
+                    .map(|name| self.state(name.trim()))
+                    .collect::<Result<_, _>>()?,
+            };
+            src = &src[end + 1..];
+        }
+
+        let (pattern, rest) = self.anchored_pattern(Some(states[0].get_name()), src)?;
+        let mut words = rest.split_whitespace();
+        let kind = match words.next() {
+            Some(SKIP_KIND) => None,
+            Some(kind) if is_name(kind) => Some(Interner::intern_name(kind)),
+            Some(kind) => return self.error(format!("invalid token type `{}`", kind)),
+            None => return self.error(String::from("missing token type")),
+        };
+
+        let mut next_state = Vec::new();
+        while let Some(directive) = words.next() {
+            let change = match directive {
+                "push" | "replace" | "pop_to" | "begin" => {
+                    let state = match words.next() {
+                        Some(state) => self.state(state)?,
+                        None => return self.error(format!("missing state after `{}`", directive)),
+                    };
+
+                    match directive {
+                        "push" => NextStateChange::Push(state),
+                        "replace" => NextStateChange::Replace(state),
+                        "pop_to" => NextStateChange::PopTo(state),
+                        _ => NextStateChange::Begin(state),
+                    }
+                }
+                "pop" => {
+                    let mut times = 1;
+                    if let Some(count) = words.clone().next().and_then(|n| n.parse().ok()) {
+                        times = count;
+                        words.next();
+                    }
+
+                    NextStateChange::Pop(times)
+                }
+                "reset" => NextStateChange::Reset,
+                _ => return self.error(format!("unknown state change `{}`", directive)),
+            };
+            next_state.push(change);
+        }
+
+        self.spec.rules.push(SpecRule {
+            line: self.line,
+            states,
+            pattern,
+            kind,
+            next_state,
+        });
+
+        Ok(())
+    }
+}
+
+impl LexerSpec {
+    // 解析词法规格文件
+    // src: 规格文件的内容
+    pub fn parse(src: &str) -> Result<Self, LexerSpecError> {
+        let mut parser = SpecParser {
+            line: 0,
+            definitions: HashMap::new(),
+            declared: false,
+            spec: LexerSpec {
+                states: vec![StateId::from(DEFAULT_STATE)],
+                ignore: None,
+                comments: Vec::new(),
+                eof: DEFAULT_EOF,
+                rules: Vec::new(),
+            },
+        };
+
+        let mut in_rules = false;
+        for (index, line) in src.lines().enumerate() {
+            parser.line = index + 1;
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.eq("%%") {
+                if in_rules {
+                    break;
+                }
+
+                in_rules = true;
+                continue;
+            }
+
+            if in_rules {
+                parser.rule(line)?;
+            } else {
+                parser.definition(line)?;
+            }
+        }
+
+        if !in_rules {
+            parser.line += 1;
+            return parser.error(String::from("missing `%%` before rules"));
+        }
+
+        Ok(parser.spec)
+    }
+
+    // 所有状态，第一个状态为初始状态
+    pub fn get_states(&self) -> &[StateId] {
+        self.states.as_slice()
+    }
+
+    pub fn get_initial_state(&self) -> StateId {
+        self.states[0]
+    }
+
+    pub fn get_ignore(&self) -> Option<&str> {
+        self.ignore.as_deref()
+    }
+
+    pub fn get_comments(&self) -> &[String] {
+        self.comments.as_slice()
+    }
+
+    pub fn get_eof(&self) -> &'static str {
+        self.eof
+    }
+
+    pub fn get_rules(&self) -> &[SpecRule] {
+        self.rules.as_slice()
+    }
+
+    // 按照规格构造词法解析器
+    pub fn build(&self) -> LexerState<()> {
+        let mut lexer = LexerState::new(self.get_initial_state(), ());
+
+        let eof = self.eof;
+        lexer.set_eof(move |_, _| Token::new(eof, ""));
+        if let Some(ignore) = self.ignore.as_ref() {
+            lexer.set_ignore(ignore);
+        }
+        self.comments.iter().for_each(|comment| {
+            lexer.set_comment(comment);
+        });
+
+        for rule in self.rules.iter() {
+            for state in rule.states.iter() {
+                let kind = rule.kind;
+                let next_state = rule.next_state.clone();
+
+                lexer.state(*state).token(&rule.pattern, move |_, token| {
+                    let state_change = StateChange::new(token.len(), 0, next_state.clone());
+
+                    match kind {
+                        Some(kind) => (TokenAction::Token(Token::new(kind, token)), state_change),
+                        None => (TokenAction::Skip, state_change),
+                    }
+                });
+            }
+        }
+
+        lexer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_build() {
+        let spec = LexerSpec::parse(
+            r#"
+            # calculator
+            DIGIT    [0-9]
+            NUMBER   {DIGIT}+(\.{DIGIT}+)?
+            %states  INITIAL STRING
+            %ignore  [ \t]+
+            %comment //[^\n]*
+            %eof     end
+            %%
+            {NUMBER}     number
+            [-+*/]       op
+            "\""         quote push STRING
+            <STRING>[^"\\]+  text
+            <STRING>\\.  escape
+            <STRING>"\"" quote pop
+            <*>\n        skip
+            "#,
+        )
+        .expect("error");
+
+        assert_eq!(StateId::from("INITIAL"), spec.get_initial_state());
+        assert_eq!(7, spec.get_rules().len());
+        assert_eq!(
+            &[StateId::from("INITIAL"), StateId::from("STRING")],
+            spec.get_rules()[6].get_states()
+        );
+
+        let mut lexer = spec.build();
+        let src = "1.5 + 2 // sum\n\"a\\\"b\n\"";
+        let tokens: Vec<_> = lexer
+            .tokens(src)
+            .map(|token| {
+                let token = token.expect("error");
+                (token.get_type(), String::from(token.get_value()))
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("number", "1.5"),
+                ("op", "+"),
+                ("number", "2"),
+                ("quote", "\""),
+                ("text", "a"),
+                ("escape", "\\\""),
+                ("text", "b\n"),
+                ("quote", "\""),
+                ("end", ""),
+            ],
+            tokens
+                .iter()
+                .map(|(kind, value)| (*kind, value.as_str()))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn spec_errors() {
+        let error = |src| LexerSpec::parse(src).expect_err("error");
+
+        assert_eq!(
+            LexerSpecError::new(3, String::from("undefined definition `DIGIT`")),
+            error("ID [a-z]+\n%%\n{DIGIT} number")
+        );
+        assert_eq!(
+            LexerSpecError::new(2, String::from("undeclared state `STRING`")),
+            error("%%\n<STRING>[a-z]+ name")
+        );
+        assert_eq!(
+            LexerSpecError::new(3, String::from("unknown state change `jump`")),
+            error("%%\n[a-z]+ name\n[0-9]+ number jump")
+        );
+        assert_eq!(
+            LexerSpecError::new(2, String::from("missing `%%` before rules")),
+            error("ID [a-z]+")
+        );
+        assert_eq!(4, error("%%\n\n# comment\n[a-z+ name").get_line());
+        assert_eq!(
+            LexerSpecError::new(2, String::from("unterminated string literal")),
+            error("%%\nab\"c d name")
+        );
+        assert_eq!(
+            LexerSpecError::new(2, String::from("unexpected `junk` after pattern")),
+            error("%comment //.*\n%ignore [ \t]+ junk\n%%")
+        );
+        assert_eq!(
+            LexerSpecError::new(1, String::from("unexpected `[a-z]` after pattern")),
+            error("ID [a-z]+ [a-z]\n%%")
+        );
+    }
+
+    #[test]
+    fn spec_literals() {
+        // 双引号之后的模式和 Token 类型仍然被读取，双引号中的内容视为一个整体
+        let spec = LexerSpec::parse(
+            r#"
+            %ignore  [ ]+
+            %eof     end
+            %%
+            ab"c d"  abcd
+            "a"+     as
+            "x."?y   xy
+            "#,
+        )
+        .expect("error");
+
+        let kinds: Vec<_> = spec.get_rules().iter().map(SpecRule::get_kind).collect();
+        assert_eq!(vec![Some("abcd"), Some("as"), Some("xy")], kinds);
+
+        let mut lexer = spec.build();
+        let tokens: Vec<_> = lexer
+            .tokens("aaa abc d x.y y")
+            .map(|token| {
+                let token = token.expect("error");
+                (token.get_type(), String::from(token.get_value()))
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("as", "aaa"),
+                ("abcd", "abc d"),
+                ("xy", "x.y"),
+                ("xy", "y"),
+                ("end", ""),
+            ],
+            tokens
+                .iter()
+                .map(|(kind, value)| (*kind, value.as_str()))
+                .collect::<Vec<_>>()
+        );
+
+        // 以 < 开头的字面模式不是状态条件
+        let spec = LexerSpec::parse(
+            r#"
+            %states  INITIAL TAG
+            %ignore  [ ]+
+            %%
+            "<"        open push TAG
+            <TAG>"<="  le
+            <TAG>"<"   nested
+            <TAG>">"   close pop
+            "#,
+        )
+        .expect("error");
+
+        let states: Vec<_> = spec.get_rules().iter().map(SpecRule::get_states).collect();
+        assert_eq!(
+            vec![
+                &[StateId::new("INITIAL")][..],
+                &[StateId::new("TAG")],
+                &[StateId::new("TAG")],
+                &[StateId::new("TAG")],
+            ],
+            states
+        );
+
+        let mut lexer = spec.build();
+        let kinds: Vec<_> = lexer
+            .tokens("< <= < >")
+            .map(|token| token.expect("error").get_type())
+            .collect();
+        assert_eq!(vec!["open", "le", "nested", "close", "eof"], kinds);
+    }
+}
@@ -10,57 +10,112 @@
  *
  * LexerStateSnapshot 用于存储 LexerState 的快照。
  *
+ * 源码的类型 H 默认为 str；H 为 [u8] 时即为 ByteLexerState，
+ * 两者共用同一套解析流程，参见 Haystack。
+ *
  */
 
-use super::{NextStateChange, Position, StateChange, Token};
-use regex::Regex;
-use std::collections::HashMap;
-
+use super::{
+    CustomCheckpoint, Haystack, IndentConfig, IndentStack, LexError, LexerBuildError, Location,
+    MatchPolicy, NextStateChange, OwnedToken, PatternError, Position, PrefixMatcher,
+    RecoveryPolicy, SourceId, StateChange, StateId, StateStack, StateStackError, Token,
+    TokenAction, TokenKind, TokenStream, TraceEvent, UTF8_BOM,
+};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Debug, Formatter};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LexerTokenSnapshot {
-    state_stack: Vec<&'static str>,
-    current_state: &'static str,
+    state_stack: Vec<StateId>,
+    current_state: StateId,
 }
 
 impl LexerTokenSnapshot {
-    pub fn new(state_stack: Vec<&'static str>, current_state: &'static str) -> Self {
+    pub fn new(state_stack: Vec<StateId>, current_state: StateId) -> Self {
         LexerTokenSnapshot {
             state_stack,
             current_state,
         }
     }
 
-    pub fn get_state_stack(&self) -> Vec<&'static str> {
+    pub fn get_state_stack(&self) -> Vec<StateId> {
         self.state_stack.clone()
     }
 
-    pub fn get_current_state(&self) -> &'static str {
+    pub fn get_current_state(&self) -> StateId {
         self.current_state
     }
 }
 
 // 词法解析器快照
-pub struct LexerStateSnapshot {
+//
+// 快照可以被复制并多次还原。C 为快照中保存的自定义状态，
+// 由 LexerState::dump 得到的快照不包含自定义状态（C 为 ()），
+// 自定义状态实现了 Clone 时可以通过 LexerState::dump_with_state 一并保存，
+// 或者通过 LexerState::dump_checkpoint 保存 CustomCheckpoint 的检查点。
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "K: TokenKind, C: serde::Serialize, H: serde::Serialize",
+        deserialize = "K: TokenKind, C: serde::Deserialize<'de>, H::Owned: serde::Deserialize<'de>"
+    ))
+)]
+pub struct LexerStateSnapshot<K = &'static str, C = (), H: ?Sized + Haystack = str> {
     offset: usize,
     line: usize,
     line_offset: usize,
+    column: usize,
+    is_eof: bool,
+    pending: Vec<OwnedToken<K, H>>,
+    indent_stack: IndentStack,
     token_snapshot: LexerTokenSnapshot,
+    custom_state: C,
 }
 
-impl LexerStateSnapshot {
+impl<K: TokenKind, H: ?Sized + Haystack> LexerStateSnapshot<K, (), H> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         offset: usize,
         line: usize,
         line_offset: usize,
+        column: usize,
+        is_eof: bool,
+        pending: Vec<OwnedToken<K, H>>,
+        indent_stack: IndentStack,
         token_snapshot: LexerTokenSnapshot,
     ) -> Self {
         LexerStateSnapshot {
             offset,
             line,
             line_offset,
+            column,
+            is_eof,
+            pending,
+            indent_stack,
             token_snapshot,
+            custom_state: (),
+        }
+    }
+
+    // 在快照中保存自定义状态
+    pub fn with_custom_state<C>(self, custom_state: C) -> LexerStateSnapshot<K, C, H> {
+        LexerStateSnapshot {
+            offset: self.offset,
+            line: self.line,
+            line_offset: self.line_offset,
+            column: self.column,
+            is_eof: self.is_eof,
+            pending: self.pending,
+            indent_stack: self.indent_stack,
+            token_snapshot: self.token_snapshot,
+            custom_state,
         }
     }
+}
 
+impl<K: TokenKind, C, H: ?Sized + Haystack> LexerStateSnapshot<K, C, H> {
     pub fn get_offset(&self) -> usize {
         self.offset
     }
@@ -73,163 +128,592 @@ impl LexerStateSnapshot {
         self.line_offset
     }
 
+    pub fn get_column(&self) -> usize {
+        self.column
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.is_eof
+    }
+
+    // 尚未返回的 Token
+    pub fn get_pending(&self) -> &[OwnedToken<K, H>] {
+        self.pending.as_slice()
+    }
+
+    // 缩进模式的运行状态
+    pub fn get_indent_stack(&self) -> &IndentStack {
+        &self.indent_stack
+    }
+
     pub fn get_token_snapshot(&self) -> &LexerTokenSnapshot {
         &self.token_snapshot
     }
+
+    pub fn get_custom_state(&self) -> &C {
+        &self.custom_state
+    }
+
+    // 移动快照所在的位置，用于源码修改后调整修改处之后的快照，参见 Position::shift
+    // line_offset: 第 line 行的起始位置增加的字节数，其他行的起始位置增加 offset
+    pub fn shift(
+        &mut self,
+        line: usize,
+        lines: isize,
+        columns: isize,
+        offset: isize,
+        line_offset: isize,
+    ) {
+        let (columns, line_offset) = if self.line.eq(&line) {
+            (columns, line_offset)
+        } else {
+            (0, offset)
+        };
+
+        for token in self.pending.iter_mut() {
+            token.shift(line, lines, columns, offset);
+        }
+        self.offset = self.offset.saturating_add_signed(offset);
+        self.line = self.line.saturating_add_signed(lines);
+        self.line_offset = self.line_offset.saturating_add_signed(line_offset);
+        self.column = self.column.saturating_add_signed(columns);
+    }
 }
 
-type TokenFactoryFunc<S> = fn(&mut S, &str) -> (Token, StateChange);
+// H 不是 Sized，无法通过 derive 实现以下 trait
+impl<K: Clone, C: Clone, H: ?Sized + Haystack> Clone for LexerStateSnapshot<K, C, H> {
+    fn clone(&self) -> Self {
+        LexerStateSnapshot {
+            offset: self.offset,
+            line: self.line,
+            line_offset: self.line_offset,
+            column: self.column,
+            is_eof: self.is_eof,
+            pending: self.pending.clone(),
+            indent_stack: self.indent_stack.clone(),
+            token_snapshot: self.token_snapshot.clone(),
+            custom_state: self.custom_state.clone(),
+        }
+    }
+}
 
-pub struct LexerTokenFactory<S> {
-    state_stack: Vec<&'static str>,
-    current_state: &'static str,
-    token_factory: HashMap<&'static str, Vec<(Regex, TokenFactoryFunc<S>)>>,
+impl<K: Debug, C: Debug, H: ?Sized + Haystack> Debug for LexerStateSnapshot<K, C, H>
+where
+    H::Owned: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LexerStateSnapshot")
+            .field("offset", &self.offset)
+            .field("line", &self.line)
+            .field("line_offset", &self.line_offset)
+            .field("column", &self.column)
+            .field("is_eof", &self.is_eof)
+            .field("pending", &self.pending)
+            .field("indent_stack", &self.indent_stack)
+            .field("token_snapshot", &self.token_snapshot)
+            .field("custom_state", &self.custom_state)
+            .finish()
+    }
 }
 
-impl<S> LexerTokenFactory<S> {
-    pub fn new(initial_status: &'static str) -> Self {
+impl<K: PartialEq, C: PartialEq, H: ?Sized + Haystack> PartialEq for LexerStateSnapshot<K, C, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset.eq(&other.offset)
+            && self.line.eq(&other.line)
+            && self.line_offset.eq(&other.line_offset)
+            && self.column.eq(&other.column)
+            && self.is_eof.eq(&other.is_eof)
+            && self.pending.eq(&other.pending)
+            && self.indent_stack.eq(&other.indent_stack)
+            && self.token_snapshot.eq(&other.token_snapshot)
+            && self.custom_state.eq(&other.custom_state)
+    }
+}
+
+impl<K: Eq, C: Eq, H: ?Sized + Haystack> Eq for LexerStateSnapshot<K, C, H> {}
+
+pub type TokenFactoryFunc<S, K, H = str> =
+    Box<dyn for<'a> Fn(&mut S, &'a H) -> (TokenAction<'a, K, H>, StateChange)>;
+
+pub type EofFactoryFunc<S, K, H = str> = Box<dyn Fn(&mut S, Position) -> Token<'static, K, H>>;
+
+pub type TraceFunc<H = str> = Box<dyn FnMut(&TraceEvent<'_, H>)>;
+
+struct TokenRule<S, K, H: ?Sized + Haystack> {
+    regex: H::Regex,
+    priority: i32,
+    factory: TokenFactoryFunc<S, K, H>,
+}
+
+// 一个状态下的所有规则
+//
+// 首次使用时将所有规则的正则表达式编译成一个 RegexSet，
+// 之后只需扫描一遍源码即可得知哪些规则能够匹配，再由这些
+// 规则各自的 Regex 得到匹配的长度。
+//
+// ignore 与 comment 为 None 时沿用 LexerState 的全局设定。
+struct StateRules<S, K, H: ?Sized + Haystack> {
+    rules: Vec<TokenRule<S, K, H>>,
+    combined: Option<Option<H::RegexSet>>,
+    ignore: Option<Vec<H::Regex>>,
+    comment: Option<Vec<H::Regex>>,
+}
+
+impl<S, K, H: ?Sized + Haystack> Default for StateRules<S, K, H> {
+    fn default() -> Self {
+        StateRules {
+            rules: Vec::new(),
+            combined: None,
+            ignore: None,
+            comment: None,
+        }
+    }
+}
+
+impl<S, K, H: ?Sized + Haystack> StateRules<S, K, H> {
+    fn push(&mut self, rule: TokenRule<S, K, H>) {
+        self.rules.push(rule);
+        self.combined = None;
+    }
+
+    // 返回能够匹配 src 的规则集合，RegexSet 构造失败时（如超出
+    // 大小限制）返回 None，由调用者逐个尝试规则
+    fn matches(&mut self, src: &H) -> Option<H::SetMatches> {
+        let rules = &self.rules;
+
+        self.combined
+            .get_or_insert_with(|| H::new_set(rules.iter().map(|rule| H::regex_str(&rule.regex))))
+            .as_ref()
+            .map(|combined| H::set_matches(combined, src))
+    }
+}
+
+pub struct LexerTokenFactory<S, K = &'static str, H: ?Sized + Haystack = str> {
+    states: StateStack,
+    match_policy: MatchPolicy,
+    token_factory: HashMap<StateId, StateRules<S, K, H>>,
+    pattern_errors: Vec<PatternError>,
+}
+
+impl<S, K: TokenKind, H: ?Sized + Haystack> LexerTokenFactory<S, K, H> {
+    pub fn new(initial_status: impl Into<StateId>) -> Self {
         LexerTokenFactory {
-            state_stack: Vec::new(),
-            current_state: initial_status,
+            states: StateStack::new(initial_status),
+            match_policy: MatchPolicy::default(),
             token_factory: HashMap::new(),
+            pattern_errors: Vec::new(),
         }
     }
 
-    pub fn reset(&mut self, initial_status: &'static str) {
-        self.state_stack.clear();
-        self.current_state = initial_status;
+    pub fn reset(&mut self, initial_status: impl Into<StateId>) {
+        self.states.reset(initial_status);
         self.token_factory.clear();
+        self.pattern_errors.clear();
     }
 
-    fn set(&mut self, state: &'static str) -> Option<&mut Vec<(Regex, TokenFactoryFunc<S>)>> {
-        if !self.token_factory.contains_key(state) {
-            self.token_factory.insert(state, Vec::new());
-        }
+    // 记录一个被拒绝的规则
+    pub fn add_pattern_error(&mut self, error: PatternError) {
+        self.pattern_errors.push(error);
+    }
 
-        self.token_factory.get_mut(state)
+    pub fn get_pattern_errors(&self) -> &[PatternError] {
+        self.pattern_errors.as_slice()
     }
 
-    pub fn add(&mut self, state: &'static str, re: Regex, token: TokenFactoryFunc<S>) {
-        self.set(state)
-            .and_then(|tokens| Some(tokens.push((re, token))));
+    pub fn add(
+        &mut self,
+        state: StateId,
+        re: H::Regex,
+        priority: i32,
+        token: TokenFactoryFunc<S, K, H>,
+    ) {
+        self.token_factory
+            .entry(state)
+            .or_default()
+            .push(TokenRule {
+                regex: re,
+                priority,
+                factory: token,
+            });
     }
 
-    fn push(&mut self, state: &'static str) {
-        self.state_stack.push(self.current_state);
-        self.current_state = state;
+    // 为状态添加一个忽略字符，将覆盖全局的忽略字符
+    pub fn add_ignore(&mut self, state: StateId, re: H::Regex) {
+        self.token_factory
+            .entry(state)
+            .or_default()
+            .ignore
+            .get_or_insert_with(Vec::new)
+            .push(re);
     }
 
-    fn pop(&mut self, times: usize) {
-        (0..times).for_each(|_| {
-            self.state_stack
-                .pop()
-                .and_then(|state| Some(self.current_state = state));
-        });
+    // 为状态添加一个注释，将覆盖全局的注释
+    pub fn add_comment(&mut self, state: StateId, re: H::Regex) {
+        self.token_factory
+            .entry(state)
+            .or_default()
+            .comment
+            .get_or_insert_with(Vec::new)
+            .push(re);
+    }
+
+    // 状态下不再跳过任何字符，也不继承全局设定
+    pub fn clear_ignore(&mut self, state: StateId) {
+        let state_rules = self.token_factory.entry(state).or_default();
+
+        state_rules.ignore = Some(Vec::new());
+        state_rules.comment = Some(Vec::new());
     }
 
-    pub fn change_state(&mut self, next_state: &NextStateChange) {
-        match next_state {
-            &NextStateChange::Push(state) => self.push(state),
-            &NextStateChange::Pop(times) => self.pop(times),
+    // 当前状态下的忽略字符和注释，为 None 时应使用全局设定
+    #[allow(clippy::type_complexity)]
+    pub fn get_ignore(&self) -> (Option<&[H::Regex]>, Option<&[H::Regex]>) {
+        match self.token_factory.get(&self.states.get_current_state_id()) {
+            Some(state_rules) => (
+                state_rules.ignore.as_deref(),
+                state_rules.comment.as_deref(),
+            ),
+            None => (None, None),
         }
     }
 
-    pub fn get<'t>(&mut self, src: &'t str) -> Option<(&'t str, &TokenFactoryFunc<S>)> {
+    pub fn set_match_policy(&mut self, match_policy: MatchPolicy) {
+        self.match_policy = match_policy;
+    }
+
+    pub fn get_match_policy(&self) -> MatchPolicy {
+        self.match_policy
+    }
+
+    pub fn get_current_state(&self) -> &'static str {
+        self.states.get_current_state()
+    }
+
+    pub fn get_current_state_id(&self) -> StateId {
+        self.states.get_current_state_id()
+    }
+
+    // 状态栈中的状态数量
+    pub fn get_state_depth(&self) -> usize {
+        self.states.get_depth()
+    }
+
+    // 设定是否在状态栈中没有足够的状态可以弹出时返回错误（默认不返回）
+    pub fn set_strict(&mut self, strict: bool) {
+        self.states.set_strict(strict);
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.states.is_strict()
+    }
+
+    pub fn get_initial_state(&self) -> StateId {
+        self.states.get_initial_state()
+    }
+
+    // 改变当前状态，严格模式下状态栈无法完成状态改变时返回错误
+    pub fn change_state(&mut self, next_state: &NextStateChange) -> Result<(), StateStackError> {
+        self.states.change_state(next_state)
+    }
+
+    // 依次改变当前状态，任何一个状态改变失败时状态保持不变，参见 StateStack::change_states
+    pub fn change_states(
+        &mut self,
+        next_state: &[NextStateChange],
+    ) -> Result<Vec<(NextStateChange, StateId, StateId)>, StateStackError> {
+        self.states.change_states(next_state)
+    }
+
+    // 按照匹配策略选择一个规则，返回规则的序号、匹配的文本和规则的动作
+    // src: 未解析的源码
+    // rejected: 已经放弃匹配的规则序号
+    pub fn get<'t>(
+        &mut self,
+        src: &'t H,
+        rejected: &[usize],
+    ) -> Option<(usize, &'t H, &TokenFactoryFunc<S, K, H>)> {
+        let (index, len) = self.select(src, rejected, |_, _, _| {})?;
+        let factory = self.get_factory(index)?;
+
+        src.get_range(0, len).map(|token| (index, token, factory))
+    }
+
+    // 当前状态下第 index 个规则的动作
+    pub fn get_factory(&self, index: usize) -> Option<&TokenFactoryFunc<S, K, H>> {
         self.token_factory
-            .get(self.current_state)
-            .and_then(|tokens| {
-                tokens.iter().find_map(|(re, factory)| {
-                    re.find(src)
-                        .and_then(|matched| Some((matched.as_str(), factory)))
-                })
-            })
+            .get(&self.states.get_current_state_id())
+            .and_then(|state_rules| state_rules.rules.get(index))
+            .map(|rule| &rule.factory)
+    }
+
+    // 当前状态下所有规则的正则表达式
+    pub fn get_patterns(&self) -> impl Iterator<Item = &str> {
+        self.token_factory
+            .get(&self.states.get_current_state_id())
+            .into_iter()
+            .flat_map(|state_rules| state_rules.rules.iter())
+            .map(|rule| H::regex_str(&rule.regex))
+    }
+
+    // 当前状态下第 index 个规则的正则表达式
+    pub fn get_pattern(&self, index: usize) -> Option<&str> {
+        self.token_factory
+            .get(&self.states.get_current_state_id())
+            .and_then(|state_rules| state_rules.rules.get(index))
+            .map(|rule| H::regex_str(&rule.regex))
+    }
+
+    // 按照匹配策略选择一个规则，返回规则的序号和匹配的长度
+    // 匹配空串的规则视为没有匹配，否则跳过空串的动作将使解析无法前进
+    // attempt: 按照匹配策略的尝试顺序，接收每个被尝试的规则的序号、正则表达式和匹配的长度
+    fn select<F>(&mut self, src: &H, rejected: &[usize], mut attempt: F) -> Option<(usize, usize)>
+    where
+        F: FnMut(usize, &str, Option<usize>),
+    {
+        let match_policy = self.match_policy;
+        let state_rules = self
+            .token_factory
+            .get_mut(&self.states.get_current_state_id())?;
+
+        let matched = state_rules.matches(src);
+
+        match_policy.select(
+            state_rules
+                .rules
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !rejected.contains(index))
+                .filter_map(|(index, rule)| {
+                    // 组合的正则表达式已经排除的规则不再单独匹配
+                    let len = match &matched {
+                        Some(set) if !H::set_matched(set, index) => None,
+                        _ => H::find(&rule.regex, src).filter(|len| len.ne(&0)),
+                    };
+                    attempt(index, H::regex_str(&rule.regex), len);
+
+                    len.map(|len| (index, len))
+                }),
+            |index| state_rules.rules[index].priority,
+        )
+    }
+
+    // 回到初始状态并清空状态栈，规则保持不变
+    pub fn rewind(&mut self) {
+        self.states.reset(self.states.get_initial_state());
     }
 
     pub fn dump(&self) -> LexerTokenSnapshot {
-        LexerTokenSnapshot::new(self.state_stack.clone(), self.current_state)
+        self.states.dump()
     }
 
     pub fn restore(&mut self, snapshot: &LexerTokenSnapshot) {
-        self.state_stack = snapshot.get_state_stack();
-        self.current_state = snapshot.get_current_state();
+        self.states.restore(snapshot);
     }
 }
 
-pub struct LexerStateSetter<'a, S> {
-    state: &'static str,
-    token_factory: &'a mut LexerTokenFactory<S>,
+pub struct LexerStateSetter<'a, S, K = &'static str, H: ?Sized + Haystack = str> {
+    state: StateId,
+    priority: i32,
+    token_factory: &'a mut LexerTokenFactory<S, K, H>,
 }
 
-impl<'a, S> LexerStateSetter<'a, S> {
-    pub fn new(state: &'static str, token_factory: &'a mut LexerTokenFactory<S>) -> Self {
+impl<'a, S, K: TokenKind, H: ?Sized + Haystack> LexerStateSetter<'a, S, K, H> {
+    pub fn new(
+        state: impl Into<StateId>,
+        token_factory: &'a mut LexerTokenFactory<S, K, H>,
+    ) -> Self {
         LexerStateSetter {
-            state,
+            state: state.into(),
+            priority: 0,
             token_factory,
         }
     }
 
-    pub fn token(&mut self, re: &str, token: TokenFactoryFunc<S>) -> &mut Self {
-        if let Ok(token_regex) = Regex::new(re) {
-            self.token_factory.add(self.state, token_regex, token);
+    // 注册一个规则，无法编译或没有以 ^ 锚定的正则表达式将被拒绝，
+    // 并记录在 LexerState::validate 的结果中
+    pub fn token<F>(&mut self, re: &str, token: F) -> &mut Self
+    where
+        F: for<'t> Fn(&mut S, &'t H) -> (TokenAction<'t, K, H>, StateChange) + 'static,
+    {
+        match H::compile(Some(self.state.get_name()), re) {
+            Ok(token_regex) => {
+                self.token_factory
+                    .add(self.state, token_regex, self.priority, Box::new(token))
+            }
+            Err(error) => self.token_factory.add_pattern_error(error),
+        }
+
+        self
+    }
+
+    // 为当前状态添加忽略字符，设定后该状态不再使用 LexerState::set_ignore 的忽略字符
+    pub fn ignore(&mut self, re: &str) -> &mut Self {
+        match H::compile(Some(self.state.get_name()), re) {
+            Ok(ignore_regex) => self.token_factory.add_ignore(self.state, ignore_regex),
+            Err(error) => self.token_factory.add_pattern_error(error),
         }
 
         self
     }
 
-    pub fn state(&mut self, state: &'static str) -> &mut Self {
-        self.state = state;
+    // 为当前状态添加注释，设定后该状态不再使用 LexerState::set_comment 的注释
+    pub fn comment(&mut self, re: &str) -> &mut Self {
+        match H::compile(Some(self.state.get_name()), re) {
+            Ok(comment_regex) => self.token_factory.add_comment(self.state, comment_regex),
+            Err(error) => self.token_factory.add_pattern_error(error),
+        }
+
+        self
+    }
+
+    // 当前状态下不跳过任何字符，适用于字符串等空白有意义的状态
+    pub fn no_ignore(&mut self) -> &mut Self {
+        self.token_factory.clear_ignore(self.state);
+
+        self
+    }
+
+    pub fn state(&mut self, state: impl Into<StateId>) -> &mut Self {
+        self.state = state.into();
+
+        self
+    }
+
+    // 设定之后注册的规则的优先级，仅在 MatchPolicy::Priority 下生效
+    // priority: 优先级，数值越大越优先，默认为 0
+    pub fn priority(&mut self, priority: i32) -> &mut Self {
+        self.priority = priority;
 
         self
     }
 }
 
-// 词法解析器
-pub struct LexerState<S> {
+// 词法解析器，H 为源码的类型
+pub struct LexerState<S, K = &'static str, H: ?Sized + Haystack = str> {
     custom_state: S,
 
     offset: usize,
+    base_offset: usize,
     line: usize,
     line_offset: usize,
-
-    ignore_regex: Option<Regex>,
-    eof: Option<fn() -> Token>,
+    column: usize,
+    track_lines: bool,
+    skip_bom: bool,
+    #[cfg(feature = "nfc")]
+    normalize_nfc: bool,
+    source: SourceId,
+
+    ignore_regex: Option<H::Regex>,
+    comment_regex: Vec<H::Regex>,
+    eof: Option<EofFactoryFunc<S, K, H>>,
     is_eof: bool,
-
-    token_factory: LexerTokenFactory<S>,
+    pending: VecDeque<OwnedToken<K, H>>,
+    indent_config: Option<IndentConfig<K>>,
+    indent_stack: IndentStack,
+    recovery: RecoveryPolicy<K, H::Regex>,
+    trace: Option<TraceFunc<H>>,
+    partial: bool,
+    prefix_matchers: HashMap<StateId, Option<PrefixMatcher>>,
+
+    token_factory: LexerTokenFactory<S, K, H>,
 }
 
-impl<S> LexerState<S> {
+impl<S, K: TokenKind> LexerState<S, K> {
     // 构造一个词法解析器
-    pub fn new(initial_status: &'static str, custom_state: S) -> Self {
+    pub fn new(initial_status: impl Into<StateId>, custom_state: S) -> Self {
+        Self::with_haystack(initial_status, custom_state)
+    }
+
+    // 设定是否将 Token 的值规范化为 NFC（默认关闭），Token 的位置仍然对应源码中的原文
+    #[cfg(feature = "nfc")]
+    pub fn set_normalize_nfc(&mut self, normalize_nfc: bool) -> &mut Self {
+        self.normalize_nfc = normalize_nfc;
+
+        self
+    }
+}
+
+impl<S, K: TokenKind> LexerState<S, K, [u8]> {
+    // 构造一个解析 &[u8] 的词法解析器，参见 ByteLexerState
+    // 二进制源码开头的 EF BB BF 不会被当作 BOM 跳过
+    pub fn new_bytes(initial_status: impl Into<StateId>, custom_state: S) -> Self {
+        let mut lexer = Self::with_haystack(initial_status, custom_state);
+        lexer.skip_bom = false;
+
+        lexer
+    }
+}
+
+impl<S, K: TokenKind, H: ?Sized + Haystack> LexerState<S, K, H> {
+    fn with_haystack(initial_status: impl Into<StateId>, custom_state: S) -> Self {
         LexerState {
             custom_state,
 
             offset: 0,
+            base_offset: 0,
             line: 1,
             line_offset: 0,
+            column: 0,
+            track_lines: true,
+            skip_bom: true,
+            #[cfg(feature = "nfc")]
+            normalize_nfc: false,
+            source: SourceId::UNKNOWN,
 
             ignore_regex: None,
+            comment_regex: Vec::new(),
             eof: None,
             is_eof: false,
+            pending: VecDeque::new(),
+            indent_config: None,
+            indent_stack: IndentStack::new(),
+            recovery: RecoveryPolicy::Fail,
+            trace: None,
+            partial: false,
+            prefix_matchers: HashMap::new(),
 
             token_factory: LexerTokenFactory::new(initial_status),
         }
     }
 
-    // 重置词法解析器
-    pub fn reset(&mut self, initial_status: &'static str, custom_state: S) {
+    // 重置词法解析器，清除规则和解析过程中的状态，状态栈的严格检查设定保持不变
+    pub fn reset(&mut self, initial_status: impl Into<StateId>, custom_state: S) {
         self.custom_state = custom_state;
 
         self.offset = 0;
+        self.base_offset = 0;
         self.line = 1;
         self.line_offset = 0;
+        self.column = 0;
 
         self.eof = None;
         self.is_eof = false;
+        self.pending.clear();
+        self.indent_config = None;
+        self.indent_stack = IndentStack::new();
+        self.recovery = RecoveryPolicy::Fail;
+        self.trace = None;
+        self.partial = false;
+        self.prefix_matchers.clear();
 
         self.token_factory.reset(initial_status);
     }
 
+    // 回到源码的开头和初始状态，规则、设定和自定义状态保持不变
+    pub fn rewind(&mut self) {
+        self.offset = 0;
+        self.base_offset = 0;
+        self.line = 1;
+        self.line_offset = 0;
+        self.column = 0;
+
+        self.is_eof = false;
+        self.pending.clear();
+        self.indent_stack = IndentStack::new();
+
+        self.token_factory.rewind();
+    }
+
     pub fn get_custom_state(&self) -> &S {
         &self.custom_state
     }
@@ -239,120 +723,799 @@ impl<S> LexerState<S> {
     }
 
     // 对当前 LexerState 状态进行快照存储
-    pub fn dump(&self) -> LexerStateSnapshot {
+    pub fn dump(&self) -> LexerStateSnapshot<K, (), H> {
         LexerStateSnapshot::new(
-            self.offset,
+            self.base_offset + self.offset,
             self.line,
-            self.line_offset,
+            self.base_offset + self.line_offset,
+            self.column,
+            self.is_eof,
+            self.pending.iter().cloned().collect(),
+            self.indent_stack.clone(),
             self.token_factory.dump(),
         )
     }
 
-    // 还原 LexerState 状态
-    pub fn restore(&mut self, snapshot: LexerStateSnapshot) {
-        self.offset = snapshot.get_offset();
+    // 保存 LexerState 状态，同时保存自定义状态
+    pub fn dump_with_state(&self) -> LexerStateSnapshot<K, S, H>
+    where
+        S: Clone,
+    {
+        self.dump().with_custom_state(self.custom_state.clone())
+    }
+
+    // 保存 LexerState 状态，同时通过 CustomCheckpoint 保存自定义状态的检查点
+    pub fn dump_checkpoint(&self) -> LexerStateSnapshot<K, S::Checkpoint, H>
+    where
+        S: CustomCheckpoint,
+    {
+        self.dump()
+            .with_custom_state(self.custom_state.checkpoint())
+    }
+
+    // 还原 LexerState 状态，快照中的自定义状态将被忽略
+    pub fn restore<C>(&mut self, snapshot: &LexerStateSnapshot<K, C, H>) {
+        self.offset = snapshot.get_offset().saturating_sub(self.base_offset);
         self.line = snapshot.get_line();
-        self.line_offset = snapshot.get_line_offset();
+        self.line_offset = snapshot.get_line_offset().saturating_sub(self.base_offset);
+        self.column = snapshot.get_column();
+        self.is_eof = snapshot.is_eof();
+        self.pending = snapshot.get_pending().iter().cloned().collect();
+        self.indent_stack = snapshot.get_indent_stack().clone();
         self.token_factory.restore(snapshot.get_token_snapshot());
     }
 
-    // 跳过忽略字符，返回offset > src.len()
+    // 还原 LexerState 状态，同时还原自定义状态
+    pub fn restore_with_state(&mut self, snapshot: &LexerStateSnapshot<K, S, H>)
+    where
+        S: Clone,
+    {
+        self.restore(snapshot);
+        self.custom_state = snapshot.get_custom_state().clone();
+    }
+
+    // 还原 LexerState 状态，同时通过 CustomCheckpoint 还原自定义状态
+    pub fn restore_checkpoint(&mut self, snapshot: &LexerStateSnapshot<K, S::Checkpoint, H>)
+    where
+        S: CustomCheckpoint,
+    {
+        self.restore(snapshot);
+        self.custom_state.restore(snapshot.get_custom_state());
+    }
+
+    // 文本指针在当前传入的源码中的字节偏移量
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    // 当前所处的状态
+    pub fn get_current_state(&self) -> &'static str {
+        self.token_factory.get_current_state()
+    }
+
+    pub fn get_current_state_id(&self) -> StateId {
+        self.token_factory.get_current_state_id()
+    }
+
+    // 是否已经产生过终止Token
+    pub fn is_eof(&self) -> bool {
+        self.is_eof
+    }
+
+    // 丢弃源码开头已经解析完成的部分，之后传入的源码应当从被丢弃部分之后开始。
+    // Position 中的字节偏移量仍然相对于最初的源码开头计算。
+    // len: 丢弃的字节数，不能超过当前已解析的字节数
+    pub fn discard(&mut self, len: usize) {
+        let len = len.min(self.offset);
+
+        self.offset -= len;
+        self.line_offset = self.line_offset.saturating_sub(len);
+        self.base_offset += len;
+    }
+
+    // 跳过忽略字符和注释，返回offset > src.len()
     // src: 源码
-    fn skip_ignore(&mut self, src: &str) -> bool {
+    fn skip_ignore(&mut self, src: &H) -> bool {
         loop {
-            if let Some(offset_increment) = self.ignore_regex.as_ref().and_then(|re| {
-                src.get(self.offset..).and_then(|offset_src| {
-                    re.find(offset_src)
-                        .and_then(|matched| Some(matched.end() - matched.start()))
+            match self.ignore_match(src) {
+                Some(offset_increment) => {
+                    self.trace(TraceEvent::Ignore {
+                        position: self.get_current_position(),
+                        state: self.token_factory.get_current_state_id(),
+                        len: offset_increment,
+                    });
+                    self.advance(src, offset_increment, 0);
+                }
+                None => break self.offset.ge(&src.as_bytes().len()),
+            }
+        }
+    }
+
+    // 在源码开头跳过 UTF-8 BOM
+    fn skip_bom(&mut self, src: &H) {
+        if self.skip_bom
+            && self.offset.eq(&0)
+            && self.base_offset.eq(&0)
+            && src.as_bytes().starts_with(UTF8_BOM.as_bytes())
+        {
+            self.offset = UTF8_BOM.len();
+            self.line_offset = self.offset;
+        }
+    }
+
+    // 当前状态下生效的忽略字符和注释
+    fn ignore_patterns(&self) -> (&[H::Regex], &[H::Regex]) {
+        let (ignore, comment) = self.token_factory.get_ignore();
+
+        (
+            ignore.unwrap_or(self.ignore_regex.as_slice()),
+            comment.unwrap_or(self.comment_regex.as_slice()),
+        )
+    }
+
+    // 当前状态下第一个能够匹配的忽略字符或注释的长度，忽略空匹配
+    // 缩进模式下匹配将在换行符处截断，换行符交由 indent_newline 处理
+    // src: 源码
+    fn ignore_match(&self, src: &H) -> Option<usize> {
+        let offset_src = src.get_from(self.offset)?;
+        let (ignore, comment) = self.ignore_patterns();
+
+        ignore
+            .iter()
+            .chain(comment.iter())
+            .filter_map(|re| H::find(re, offset_src))
+            .map(|end| match self.indent_config {
+                Some(_) => offset_src.as_bytes()[..end]
+                    .iter()
+                    .position(|byte| matches!(byte, b'\n' | b'\r'))
+                    .unwrap_or(end),
+                None => end,
+            })
+            .find(|end| end.ne(&0))
+    }
+
+    // 缩进模式是否在当前状态下生效
+    fn indent_active(&self) -> bool {
+        match self.indent_config.as_ref() {
+            Some(config) => {
+                !config.is_ignore_nested() || self.token_factory.get_state_depth().eq(&0)
+            }
+            None => false,
+        }
+    }
+
+    // 在行首计算缩进宽度，产生 INDENT 或 DEDENT
+    // 空行以及只有注释的行不影响缩进
+    // src: 源码
+    fn indent_line(&mut self, src: &H) -> Result<(), LexError> {
+        let config = match self.indent_config {
+            Some(config) if self.indent_stack.is_at_line_start() && self.indent_active() => config,
+            _ => return Ok(()),
+        };
+        self.indent_stack.set_at_line_start(false);
+
+        let rest = match src.get_from(self.offset) {
+            Some(rest) => rest,
+            None => return Err(self.no_match_error(src)),
+        };
+        let (len, width) = match config.measure(rest) {
+            Some(measured) => measured,
+            None => {
+                return Err(LexError::TabIndent {
+                    position: self.get_current_position(),
+                    state: self.token_factory.get_current_state(),
                 })
-            }) {
-                self.offset += offset_increment;
+            }
+        };
+
+        let line = rest.get_from(len).unwrap_or(H::empty());
+        let (_, comment) = self.ignore_patterns();
+        if matches!(line.as_bytes().first(), None | Some(b'\n' | b'\r'))
+            || comment.iter().any(|re| H::is_match(re, line))
+        {
+            return Ok(());
+        }
+
+        let begin_position = self.get_current_position();
+        self.advance(src, len, 0);
+        let end_position = self.get_current_position();
+
+        if width > self.indent_stack.get_width() {
+            self.indent_stack.push(width);
+
+            let indent = rest.get_range(0, len).unwrap_or(H::empty());
+            let mut token = Token::new_owned(config.get_indent(), indent.to_owned());
+            token.set_location(Location::new_with_source(
+                self.source,
+                begin_position,
+                end_position,
+            ));
+            self.pending.push_back(token);
+        } else {
+            while width < self.indent_stack.get_width() {
+                self.indent_stack.pop();
+
+                let mut token = Token::new_owned(config.get_dedent(), H::empty().to_owned());
+                token.set_location(Location::new_with_source(
+                    self.source,
+                    end_position,
+                    end_position,
+                ));
+                self.pending.push_back(token);
+            }
+
+            if width.ne(&self.indent_stack.get_width()) {
+                return Err(LexError::InconsistentDedent {
+                    position: end_position,
+                    state: self.token_factory.get_current_state(),
+                    width,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // 缩进模式下跳过一个换行符，非空行结束时产生 NEWLINE
+    // 状态栈非空且忽略嵌套时，换行符被直接跳过
+    // 返回是否跳过了换行符
+    // src: 源码
+    fn indent_newline(&mut self, src: &H) -> bool {
+        let config = match self.indent_config {
+            Some(config) => config,
+            None => return false,
+        };
+
+        let rest = src.get_from(self.offset).unwrap_or(H::empty());
+        let len = match rest.as_bytes() {
+            [b'\r', b'\n', ..] => 2,
+            [b'\n' | b'\r', ..] => 1,
+            _ => return false,
+        };
+
+        let begin_position = self.get_current_position();
+        self.advance(src, len, 1);
+
+        if self.indent_active() {
+            if self.indent_stack.has_line_token() {
+                let newline = rest.get_range(0, len).unwrap_or(H::empty());
+                let mut token = Token::new_owned(config.get_newline(), newline.to_owned());
+                token.set_location(Location::new_with_source(
+                    self.source,
+                    begin_position,
+                    self.get_current_position(),
+                ));
+                self.pending.push_back(token);
+            }
+
+            self.indent_stack.set_at_line_start(true);
+            self.indent_stack.set_line_has_token(false);
+        }
+
+        true
+    }
+
+    // 源码结束时结束最后一行，并关闭所有缩进
+    // 返回是否产生了 Token
+    fn indent_eof(&mut self) -> bool {
+        let config = match self.indent_config {
+            Some(config) => config,
+            None => return false,
+        };
+
+        let position = self.get_current_position();
+        let location = Location::new_with_source(self.source, position, position);
+        if self.indent_stack.has_line_token() {
+            let mut token = Token::new_owned(config.get_newline(), H::empty().to_owned());
+            token.set_location(location);
+            self.pending.push_back(token);
+        }
+        while self.indent_stack.pop().is_some() {
+            let mut token = Token::new_owned(config.get_dedent(), H::empty().to_owned());
+            token.set_location(location);
+            self.pending.push_back(token);
+        }
+        self.indent_stack.set_line_has_token(false);
+
+        !self.pending.is_empty()
+    }
+
+    // 向前移动文本指针，并更新行号和列号
+    // src: 源码
+    // increment: 移动的字节数
+    // increment_lines: 手动指定的增加行数，仅在关闭自动行号计算时使用
+    // 列号按照字符计算，H 为 [u8] 时每个字节计为一列
+    fn advance(&mut self, src: &H, increment: usize, increment_lines: usize) {
+        let begin = self.offset;
+        self.offset += increment;
+        let consumed = src.get_range(begin, self.offset).unwrap_or(H::empty());
+        let columns =
+            (0..consumed.as_bytes().len()).filter(|index| consumed.is_char_boundary(*index));
+
+        if !self.track_lines {
+            if increment_lines.ne(&0) {
+                self.line += increment_lines;
+                self.line_offset = self.offset;
+                self.column = 0;
             } else {
-                break self.offset.ge(&src.len());
+                self.column += columns.count();
             }
+
+            return;
         }
+
+        // \r\n 在遇到 \r 时已经计为一行，其后的 \n 不再重复计算
+        let mut after_cr = begin.ne(&0) && src.as_bytes().get(begin - 1).eq(&Some(&b'\r'));
+        for index in columns {
+            let byte = consumed.as_bytes()[index];
+            match byte {
+                b'\n' if after_cr => self.line_offset = begin + index + 1,
+                b'\n' | b'\r' => {
+                    self.line += 1;
+                    self.line_offset = begin + index + 1;
+                    self.column = 0;
+                }
+                _ => self.column += 1,
+            }
+            after_cr = byte.eq(&b'\r');
+        }
+    }
+
+    // 设定所解析源码的标识，将记录在 Token 的 Location 中
+    // source: 由 SourceMap 分配的源码标识
+    pub fn set_source(&mut self, source: SourceId) -> &mut Self {
+        self.source = source;
+
+        self
+    }
+
+    // 设定是否根据已解析的文本自动计算行号（默认开启）
+    // 关闭后行号只能通过 TokenFactory::lines 手动指定，开启时该值将被忽略
+    // track_lines: 是否自动计算行号
+    pub fn set_track_lines(&mut self, track_lines: bool) -> &mut Self {
+        self.track_lines = track_lines;
+
+        self
+    }
+
+    // 设定是否跳过源码开头的 UTF-8 BOM（默认开启），BOM 不计入列号
+    pub fn set_skip_bom(&mut self, skip_bom: bool) -> &mut Self {
+        self.skip_bom = skip_bom;
+
+        self
     }
 
     // 设定忽略字符
     // re: 忽略字符正则表达式
     pub fn set_ignore(&mut self, re: &str) -> &mut Self {
-        if let Ok(reg) = Regex::new(re) {
-            self.ignore_regex = Some(reg);
+        match H::compile(None, re) {
+            Ok(reg) => self.ignore_regex = Some(reg),
+            Err(error) => self.token_factory.add_pattern_error(error),
         }
+        self.prefix_matchers.clear();
+
+        self
+    }
+
+    // 开启缩进模式
+    // indent: 缩进模式的设定
+    pub fn set_indent(&mut self, indent: &IndentConfig<K>) -> &mut Self {
+        self.indent_config = Some(*indent);
+        self.indent_stack = IndentStack::new();
 
         self
     }
 
+    // 设定注释，可多次调用以添加多种注释
+    // re: 注释正则表达式
+    pub fn set_comment(&mut self, re: &str) -> &mut Self {
+        match H::compile(None, re) {
+            Ok(reg) => self.comment_regex.push(reg),
+            Err(error) => self.token_factory.add_pattern_error(error),
+        }
+        self.prefix_matchers.clear();
+
+        self
+    }
+
+    // 检查注册过程中是否有被拒绝的正则表达式
+    pub fn validate(&self) -> Result<(), LexerBuildError> {
+        let errors = self.token_factory.get_pattern_errors();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(LexerBuildError::new(errors.to_vec()))
+        }
+    }
+
     // 设定终止Token
-    // eof: 构造终止Token的方法
-    pub fn set_eof(&mut self, eof: fn() -> Token) -> &mut Self {
-        self.eof = Some(eof);
+    // eof: 构造终止Token的方法，参数为自定义状态和源码结束的位置
+    pub fn set_eof<F>(&mut self, eof: F) -> &mut Self
+    where
+        F: Fn(&mut S, Position) -> Token<'static, K, H> + 'static,
+    {
+        self.eof = Some(Box::new(eof));
 
         self
     }
 
-    pub fn state(&mut self, state: &'static str) -> LexerStateSetter<S> {
+    // 设定是否严格检查状态栈，开启后弹出的状态超过状态栈中的状态数量时
+    // 将返回 LexError::StateStackUnderflow，而不是弹出全部状态；pop_to 的目标状态
+    // 不在状态栈中时将返回 LexError::MissingState，而不是保持不变
+    pub fn set_strict_states(&mut self, strict: bool) -> &mut Self {
+        self.token_factory.set_strict(strict);
+
+        self
+    }
+
+    // 设定规则的匹配策略
+    // match_policy: 匹配策略
+    pub fn set_match_policy(&mut self, match_policy: MatchPolicy) -> &mut Self {
+        self.token_factory.set_match_policy(match_policy);
+
+        self
+    }
+
+    // 设定没有任何规则能够匹配时的恢复策略，默认不进行恢复
+    pub fn set_recovery(&mut self, recovery: RecoveryPolicy<K, H::Regex>) -> &mut Self {
+        self.recovery = recovery;
+
+        self
+    }
+
+    // 恢复时跳过字符直到同步模式能够匹配，产生类型为 kind 的错误 Token
+    pub fn set_recovery_sync(&mut self, kind: K, re: &str) -> &mut Self {
+        match H::compile(None, re) {
+            Ok(reg) => self.recovery = RecoveryPolicy::Sync(kind, reg),
+            Err(error) => self.token_factory.add_pattern_error(error),
+        }
+
+        self
+    }
+
+    pub fn get_recovery(&self) -> &RecoveryPolicy<K, H::Regex> {
+        &self.recovery
+    }
+
+    // 设定调试回调，接收状态改变、规则尝试与匹配、跳过忽略字符以及 EOF 等事件
+    pub fn set_trace<F>(&mut self, trace: F) -> &mut Self
+    where
+        F: FnMut(&TraceEvent<'_, H>) + 'static,
+    {
+        self.trace = Some(Box::new(trace));
+
+        self
+    }
+
+    pub fn clear_trace(&mut self) -> &mut Self {
+        self.trace = None;
+
+        self
+    }
+
+    pub fn state(&mut self, state: impl Into<StateId>) -> LexerStateSetter<'_, S, K, H> {
+        self.prefix_matchers.clear();
+
         LexerStateSetter::new(state, &mut self.token_factory)
     }
 
-    fn state_change(&mut self, change: &StateChange) {
-        self.offset += change.get_increment_offset();
+    // 改变状态并移动文本指针，任何一个状态改变失败时状态和文本指针均保持不变
+    fn state_change(&mut self, src: &H, change: &StateChange) -> Result<(), StateStackError> {
+        let changes = self.token_factory.change_states(change.get_next_state())?;
+        self.advance(
+            src,
+            change.get_increment_offset(),
+            change.get_increment_lines(),
+        );
+
+        let position = self.get_current_position();
+        for (change, from, to) in changes {
+            self.trace(TraceEvent::StateChange {
+                position,
+                change,
+                from,
+                to,
+            });
+        }
 
-        if change.get_increment_lines().ne(&0) {
-            self.line += change.get_increment_lines();
-            self.line_offset = self.offset;
+        Ok(())
+    }
+
+    // 将调试事件交给回调，未设定回调时不做任何事
+    fn trace(&mut self, event: TraceEvent<'_, H>) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace(&event);
         }
+    }
 
-        change
-            .get_next_state()
-            .iter()
-            .for_each(|state| self.token_factory.change_state(state));
+    // 当前文本指针所在的位置
+    pub fn get_current_position(&self) -> Position {
+        Position::new(self.line, self.column, self.base_offset + self.offset)
     }
 
-    fn get_current_position(&self) -> Position {
-        Position::new(self.line, self.offset - self.line_offset)
+    // 设定源码是否只读入了一部分（默认为否）
+    // 开启后，如果当前状态下有规则或忽略字符在未解析源码的末尾仍可能继续匹配，
+    // next 将返回 LexError::Incomplete，由调用者读入更多源码后重新解析
+    pub fn set_partial(&mut self, partial: bool) -> &mut Self {
+        self.partial = partial;
+
+        self
     }
 
-    fn next_token(&mut self, src: &str) -> Option<(Token, StateChange)> {
-        src.get(self.offset..).and_then(|src| {
-            let custom_state = &mut self.custom_state;
+    // 当前状态下是否有规则或忽略字符在 src 的末尾仍可能继续匹配
+    fn may_extend(&mut self, src: &H) -> bool {
+        let state = self.token_factory.get_current_state_id();
+        if !self.prefix_matchers.contains_key(&state) {
+            let (ignore, comment) = self.ignore_patterns();
+            let patterns: Vec<&str> = ignore
+                .iter()
+                .chain(comment)
+                .map(H::regex_str)
+                .chain(self.token_factory.get_patterns())
+                .collect();
+            let matcher = PrefixMatcher::new(&patterns, H::UTF8);
+            self.prefix_matchers.insert(state, matcher);
+        }
 
-            self.token_factory
-                .get(src)
-                .and_then(|(token, factory)| Some(factory(custom_state, token)))
-        })
+        match self.prefix_matchers.get_mut(&state) {
+            Some(Some(matcher)) => matcher.is_live(src.as_bytes()),
+            _ => false,
+        }
     }
 
-    fn next_eof(&mut self) -> Result<Token, ()> {
+    // 执行匹配的规则的动作，规则放弃匹配时改用下一个可以匹配的规则
+    fn next_action<'s>(
+        &mut self,
+        src: &'s H,
+    ) -> Result<(TokenAction<'s, K, H>, StateChange), LexError> {
+        let rest = match src.get_from(self.offset) {
+            Some(rest) => rest,
+            None => return Err(self.no_match_error(src)),
+        };
+
+        let position = self.get_current_position();
+        if self.partial && self.may_extend(rest) {
+            return Err(LexError::Incomplete {
+                position,
+                state: self.token_factory.get_current_state(),
+            });
+        }
+
+        let mut rejected = Vec::new();
+        loop {
+            let state = self.token_factory.get_current_state_id();
+            // 选择规则时依次产生被尝试的规则，之后由 RuleMatch 或 RuleReject 给出被选中的规则
+            let trace = &mut self.trace;
+            let selected = self
+                .token_factory
+                .select(rest, &rejected, |rule, pattern, len| {
+                    if let Some(trace) = trace.as_mut() {
+                        trace(&TraceEvent::RuleAttempt {
+                            position,
+                            state,
+                            rule,
+                            pattern,
+                            token: len.and_then(|len| rest.get_range(0, len)),
+                        });
+                    }
+                });
+            let (index, len) = match selected {
+                Some(selected) => selected,
+                None => {
+                    self.trace(TraceEvent::NoMatch { position, state });
+
+                    break Err(self.no_match_error(src));
+                }
+            };
+            let token = match rest.get_range(0, len) {
+                Some(token) => token,
+                None => break Err(self.no_match_error(src)),
+            };
+
+            let result = match self.token_factory.get_factory(index) {
+                Some(factory) => factory(&mut self.custom_state, token),
+                None => break Err(self.no_match_error(src)),
+            };
+            match result {
+                (TokenAction::Reject, _) => {
+                    self.trace(TraceEvent::RuleReject {
+                        position,
+                        state,
+                        rule: index,
+                    });
+                    rejected.push(index);
+                }
+                result => {
+                    self.trace(TraceEvent::RuleMatch {
+                        position,
+                        state,
+                        rule: index,
+                        len,
+                    });
+
+                    break Ok(result);
+                }
+            }
+        }
+    }
+
+    fn next_eof(&mut self) -> Result<Token<'static, K, H>, LexError> {
         self.is_eof = true;
 
-        self.eof.and_then(|eof| Some(eof())).ok_or(())
+        let position = self.get_current_position();
+        self.trace(TraceEvent::Eof {
+            position,
+            state: self.token_factory.get_current_state_id(),
+        });
+        match self.eof.as_ref() {
+            Some(eof) => {
+                let mut token = eof(&mut self.custom_state, position);
+                token.set_location(Location::new_with_source(self.source, position, position));
+
+                Ok(token)
+            }
+            None => Err(LexError::MissingEof {
+                position,
+                state: self.token_factory.get_current_state(),
+            }),
+        }
+    }
+
+    // 按照恢复策略跳过无法匹配的文本，返回错误 Token
+    // 只有没有任何规则能够匹配的错误可以恢复
+    fn recover<'s>(&mut self, src: &'s H, error: &LexError) -> Option<Token<'s, K, H>> {
+        if !matches!(error, LexError::NoMatch { .. }) {
+            return None;
+        }
+
+        let kind = self.recovery.get_kind()?;
+        let rest = src.get_from(self.offset)?;
+        let len = self.recovery.skip_len(rest)?;
+
+        let begin_position = self.get_current_position();
+        self.trace(TraceEvent::Recover {
+            position: begin_position,
+            state: self.token_factory.get_current_state_id(),
+            len,
+        });
+        self.advance(src, len, 0);
+        self.indent_stack.set_line_has_token(true);
+
+        let mut token = Token::new(kind, rest.get_range(0, len)?);
+        token.set_location(Location::new_with_source(
+            self.source,
+            begin_position,
+            self.get_current_position(),
+        ));
+
+        Some(token)
+    }
+
+    // 构造匹配失败时的错误信息
+    fn no_match_error(&self, src: &H) -> LexError {
+        let position = self.get_current_position();
+        let state = self.token_factory.get_current_state();
+
+        match src.get_from(self.offset) {
+            Some(rest) => LexError::NoMatch {
+                position,
+                state,
+                snippet: rest.snippet(),
+            },
+            None => LexError::InvalidUtf8Boundary {
+                position,
+                state,
+                offset: self.base_offset + self.offset,
+            },
+        }
+    }
+
+    // 以迭代器的方式从源码中获取所有Token
+    // src: 源码
+    pub fn tokens<'s>(&mut self, src: &'s H) -> TokenStream<'_, 's, S, K, H> {
+        TokenStream::new(self, src)
     }
 
     // 从源码中获取一个Token
     // src: 源码
-    pub fn next(&mut self, src: &str) -> Result<Token, ()> {
-        if self.is_eof || self.offset.ge(&src.len()) || self.skip_ignore(src) {
-            self.next_eof()
-        } else {
+    pub fn next<'s>(&mut self, src: &'s H) -> Result<Token<'s, K, H>, LexError> {
+        loop {
+            if let Some(token) = self.pending.pop_front() {
+                break Ok(token);
+            }
+
+            if self.is_eof {
+                break self.next_eof();
+            }
+
+            self.skip_bom(src);
+            self.indent_line(src)?;
+            if !self.pending.is_empty() {
+                continue;
+            }
+
+            if self.offset.ge(&src.as_bytes().len()) || self.skip_ignore(src) {
+                if self.indent_eof() {
+                    continue;
+                }
+
+                break self.next_eof();
+            }
+
+            if self.indent_newline(src) {
+                continue;
+            }
+
             let begin_position = self.get_current_position();
+            let (action, state_change) = match self.next_action(src) {
+                Ok(result) => result,
+                Err(error) => match self.recover(src, &error) {
+                    Some(token) => break Ok(token),
+                    None => break Err(error),
+                },
+            };
+            if let Err(error) = self.state_change(src, &state_change) {
+                break Err(LexError::state_stack(
+                    error,
+                    begin_position,
+                    self.token_factory.get_current_state(),
+                ));
+            }
 
-            self.next_token(src)
-                .and_then(|(mut token, state_change)| {
-                    self.state_change(&state_change);
-                    token.set_location(begin_position, self.get_current_position());
+            let location =
+                Location::new_with_source(self.source, begin_position, self.get_current_position());
+            #[cfg(feature = "nfc")]
+            let normalize_nfc = self.normalize_nfc;
+            let locate = |mut token: Token<'s, K, H>| {
+                if !token.has_location() {
+                    token.set_location(location);
+                }
 
-                    Some(token)
-                })
-                .ok_or(())
+                #[cfg(feature = "nfc")]
+                if normalize_nfc {
+                    return H::into_nfc(token);
+                }
+
+                token
+            };
+
+            match action {
+                TokenAction::Token(token) => {
+                    self.indent_stack.set_line_has_token(true);
+
+                    break Ok(locate(token));
+                }
+                TokenAction::Emit(tokens) => {
+                    let mut tokens = tokens.into_iter().map(locate);
+                    if let Some(token) = tokens.next() {
+                        self.indent_stack.set_line_has_token(true);
+
+                        self.pending.extend(tokens.map(Token::into_owned));
+
+                        break Ok(token);
+                    }
+                }
+                TokenAction::Error(message) => {
+                    break Err(LexError::Action {
+                        position: begin_position,
+                        state: self.token_factory.get_current_state(),
+                        message,
+                    })
+                }
+                TokenAction::Skip | TokenAction::Reject => {}
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::TokenFactory;
+    use super::super::{PatternErrorKind, TokenFactory};
     use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashSet;
+    use std::rc::Rc;
 
     #[test]
     fn parse_calc() {
@@ -367,7 +1530,7 @@ mod tests {
 
         let mut state = LexerState::new(INITIAL_STATE, ());
         state
-            .set_eof(|| Token::new("eof", ""))
+            .set_eof(|_, _| Token::new("eof", ""))
             .set_ignore(r"^( |\t)")
             .state(INITIAL_STATE)
             .token(NUMBER_TOKEN, |_, token| {
@@ -413,7 +1576,7 @@ mod tests {
         let mut state = LexerState::new("init", ());
 
         state
-            .set_eof(|| Token::new("eof", ""))
+            .set_eof(|_, _| Token::new("eof", ""))
             .set_ignore(r"^( |\t)");
 
         state
@@ -474,4 +1637,828 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn parse_error() {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_ignore(r"^( |\t)")
+            .state("init")
+            .token(r"^[a-z]+", |_, token| {
+                TokenFactory::new("name").build(token)
+            });
+
+        let src = "abc  123456789012345678";
+        assert!(state.next(src).is_ok());
+        match state.next(src) {
+            Err(LexError::NoMatch {
+                position,
+                state,
+                snippet,
+            }) => {
+                assert_eq!(Position::new(1, 5, 5), position);
+                assert_eq!("init", state);
+                assert_eq!("1234567890123456...", snippet);
+            }
+            _ => panic!("error"),
+        }
+
+        let mut state = LexerState::new("init", ());
+        state.state("init").token(r"^[a-z]+", |_, token| {
+            TokenFactory::new("name").build(token)
+        });
+
+        assert!(state.next("abc").is_ok());
+        assert_eq!(
+            Err(LexError::MissingEof {
+                position: Position::new(1, 3, 3),
+                state: "init",
+            }),
+            state
+                .next("abc")
+                .map(|token| String::from(token.get_type()))
+        );
+    }
+
+    fn assert_tokens(state: &mut LexerState<()>, src: &str, tokens: Vec<(&str, &str)>) {
+        for (token_type, token_value) in tokens {
+            if let Ok(token) = state.next(src) {
+                assert_eq!(token_type, token.get_type());
+                assert_eq!(token_value, token.get_value());
+            } else {
+                panic!("error");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_match_policy() {
+        let new_state = |match_policy| {
+            let mut state = LexerState::new("init", ());
+            state
+                .set_eof(|_, _| Token::new("eof", ""))
+                .set_ignore(r"^ ")
+                .set_match_policy(match_policy)
+                .state("init")
+                .token(r"^[a-z]+", |_, token| {
+                    TokenFactory::new("name").build(token)
+                })
+                .token(r"^=", |_, token| TokenFactory::new("assign").build(token))
+                .token(r"^==", |_, token| TokenFactory::new("equal").build(token))
+                .priority(1)
+                .token(r"^if", |_, token| TokenFactory::new("if").build(token));
+            state
+        };
+
+        let src = "if ifx == y";
+
+        assert_tokens(
+            &mut new_state(MatchPolicy::FirstMatch),
+            src,
+            vec![("name", "if"), ("name", "ifx"), ("assign", "=")],
+        );
+        assert_tokens(
+            &mut new_state(MatchPolicy::LongestMatch),
+            src,
+            vec![
+                ("name", "if"),
+                ("name", "ifx"),
+                ("equal", "=="),
+                ("name", "y"),
+                ("eof", ""),
+            ],
+        );
+        assert_tokens(
+            &mut new_state(MatchPolicy::Priority),
+            src,
+            vec![
+                ("if", "if"),
+                ("if", "if"),
+                ("name", "x"),
+                ("equal", "=="),
+                ("name", "y"),
+                ("eof", ""),
+            ],
+        );
+    }
+
+    #[test]
+    fn parse_invalid_pattern() {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"\s+")
+            .state("init")
+            .token(r"^[a-z+", |_, token| TokenFactory::new("name").build(token))
+            .token(r"^\d+", |_, token| TokenFactory::new("number").build(token))
+            .state("other")
+            .token(r"(?m)^;", |_, token| TokenFactory::new("end").build(token));
+
+        let errors = state.validate().expect_err("error");
+        let errors = errors.get_errors();
+        assert_eq!(3, errors.len());
+        assert_eq!(None, errors[0].get_state());
+        assert_eq!(&PatternErrorKind::Unanchored, errors[0].get_kind());
+        assert_eq!(Some("init"), errors[1].get_state());
+        assert_eq!(r"^[a-z+", errors[1].get_pattern());
+        match errors[1].get_kind() {
+            PatternErrorKind::InvalidRegex(_) => {}
+            _ => panic!("error type"),
+        }
+        assert_eq!(Some("other"), errors[2].get_state());
+        assert_eq!(&PatternErrorKind::Unanchored, errors[2].get_kind());
+
+        assert_tokens(&mut state, "123", vec![("number", "123"), ("eof", "")]);
+    }
+
+    #[test]
+    fn parse_lines() {
+        let new_state = |track_lines| {
+            let mut state = LexerState::new("init", ());
+            state
+                .set_eof(|_, _| Token::new("eof", ""))
+                .set_ignore(r"^\s+")
+                .set_track_lines(track_lines)
+                .state("init")
+                .token(r"^\w+", |_, token| TokenFactory::new("name").build(token))
+                .token(r"^/\*(?s:.)*?\*/", |_, token| {
+                    TokenFactory::new("comment").lines(1).build(token)
+                });
+            state
+        };
+
+        let src = "ab  cd\r\n  éf\rgh\n\n  /*\n\n*/ ij";
+        let mut state = new_state(true);
+        for (begin, end) in [
+            ((1, 0, 0), (1, 2, 2)),
+            ((1, 4, 4), (1, 6, 6)),
+            ((2, 2, 10), (2, 4, 13)),
+            ((3, 0, 14), (3, 2, 16)),
+            ((5, 2, 20), (7, 2, 26)),
+            ((7, 3, 27), (7, 5, 29)),
+        ] {
+            let location = state.next(src).expect("error").get_location();
+            assert_eq!(
+                Position::new(begin.0, begin.1, begin.2),
+                location.get_begin()
+            );
+            assert_eq!(Position::new(end.0, end.1, end.2), location.get_end());
+        }
+
+        let mut state = new_state(false);
+        for (begin, end) in [
+            ((1, 0, 0), (1, 2, 2)),
+            ((1, 4, 4), (1, 6, 6)),
+            ((1, 10, 10), (1, 12, 13)),
+            ((1, 13, 14), (1, 15, 16)),
+            ((1, 19, 20), (2, 0, 26)),
+            ((2, 1, 27), (2, 3, 29)),
+        ] {
+            let location = state.next(src).expect("error").get_location();
+            assert_eq!(
+                Position::new(begin.0, begin.1, begin.2),
+                location.get_begin()
+            );
+            assert_eq!(Position::new(end.0, end.1, end.2), location.get_end());
+        }
+    }
+
+    #[test]
+    fn parse_closure() {
+        let keywords: HashSet<&str> = vec!["if", "else"].into_iter().collect();
+        let names = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&names);
+
+        let mut state = LexerState::new("init", 0);
+        state
+            .set_eof(|lines: &mut usize, position| {
+                *lines = position.get_line();
+                Token::new("eof", "")
+            })
+            .set_ignore(r"^\s+")
+            .state("init")
+            .token(r"^\w+", move |_, token| {
+                if keywords.contains(token) {
+                    TokenFactory::new("keyword").build(token)
+                } else {
+                    counter.set(counter.get() + 1);
+                    TokenFactory::new("name").build(token)
+                }
+            });
+
+        let src = "if a\nelse b";
+        let tokens: Vec<_> = state
+            .tokens(src)
+            .map(|token| token.expect("error"))
+            .collect();
+        assert_eq!(
+            vec!["keyword", "name", "keyword", "name", "eof"],
+            tokens
+                .iter()
+                .map(|token| token.get_type())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(2, names.get());
+        assert_eq!(&2, state.get_custom_state());
+        assert_eq!(
+            Position::new(2, 6, 11),
+            tokens[4].get_location().get_begin()
+        );
+    }
+
+    #[test]
+    fn parse_actions() {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^ +")
+            .state("init")
+            .token(r"^//[^\n]*\n?", |_, token| {
+                TokenFactory::new("comment").lines(1).skip(token)
+            })
+            .token(r"^\w+", |_, token| {
+                if token.eq("skip") {
+                    TokenFactory::new("keyword").reject()
+                } else if token.eq("bad") {
+                    TokenFactory::new("keyword").error(token, "reserved word")
+                } else {
+                    TokenFactory::new("keyword").build(token)
+                }
+            })
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token))
+            .token(r"^\+\+", |_, token| {
+                TokenFactory::new("op").emit(
+                    token,
+                    vec![
+                        Token::new("plus", &token[..1]),
+                        Token::new("plus", &token[1..]),
+                    ],
+                )
+            });
+
+        let src = "// comment\nif skip ++ bad x";
+        assert_tokens(
+            &mut state,
+            src,
+            vec![
+                ("keyword", "if"),
+                ("name", "skip"),
+                ("plus", "+"),
+                ("plus", "+"),
+            ],
+        );
+
+        match state.next(src) {
+            Err(LexError::Action {
+                position, message, ..
+            }) => {
+                assert_eq!(Position::new(2, 11, 22), position);
+                assert_eq!("reserved word", message);
+            }
+            _ => panic!("error"),
+        }
+
+        let token = state.next(src).expect("error");
+        assert_eq!(("keyword", "x"), (token.get_type(), token.get_value()));
+        assert_eq!("eof", state.next(src).expect("error").get_type());
+    }
+
+    #[test]
+    fn parse_empty_match() {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .state("init")
+            .token(r"^ *", |_, token| TokenFactory::new("space").skip(token))
+            .token(r"^-*", |_, token| {
+                TokenFactory::new("dash").emit(token, Vec::new())
+            })
+            .token(r"^[a-z]+", |_, token| {
+                TokenFactory::new("name").build(token)
+            });
+
+        // 匹配空串的规则视为没有匹配，不会使解析停留在原地
+        let src = "a  b";
+        assert_tokens(&mut state, src, vec![("name", "a"), ("name", "b")]);
+        assert_eq!("eof", state.next(src).expect("error").get_type());
+
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .state("init")
+            .token(r"^ *", |_, token| TokenFactory::new("space").skip(token));
+        match state.next("x") {
+            Err(LexError::NoMatch { position, .. }) => assert_eq!(0, position.get_offset()),
+            _ => panic!("error"),
+        }
+    }
+
+    #[test]
+    fn parse_state_ignore() {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^\s+")
+            .set_comment(r"^#[^\n]*")
+            .state("init")
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token))
+            .token(r"^\[", |_, token| {
+                TokenFactory::new("open").push_state("list").build(token)
+            })
+            .token("^\"", |_, token| {
+                TokenFactory::new("quote").push_state("string").build(token)
+            })
+            .state("list")
+            .comment(r"^//[^\n]*")
+            .token(r"^\w+", |_, token| TokenFactory::new("item").build(token))
+            .token(r"^#", |_, token| TokenFactory::new("hash").build(token))
+            .token(r"^\]", |_, token| {
+                TokenFactory::new("close").pop_state(1).build(token)
+            })
+            .state("string")
+            .no_ignore()
+            .token("^[^\"]+", |_, token| TokenFactory::new("text").build(token))
+            .token("^\"", |_, token| {
+                TokenFactory::new("quote").pop_state(1).build(token)
+            });
+        assert!(state.validate().is_ok());
+
+        let src = "a # note\n[ b // note\n # ] \" c # d \"";
+        assert_tokens(
+            &mut state,
+            src,
+            vec![
+                ("name", "a"),
+                ("open", "["),
+                ("item", "b"),
+                ("hash", "#"),
+                ("close", "]"),
+                ("quote", "\""),
+                ("text", " c # d "),
+                ("quote", "\""),
+                ("eof", ""),
+            ],
+        );
+    }
+
+    #[test]
+    fn parse_indent() {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^[ \t]+")
+            .set_comment(r"^#[^\n]*")
+            .set_indent(IndentConfig::new("indent", "dedent", "newline").tab_width(4))
+            .state("init")
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token))
+            .token(r"^:", |_, token| TokenFactory::new("colon").build(token))
+            .token(r"^\(", |_, token| {
+                TokenFactory::new("open").push_state("paren").build(token)
+            })
+            .state("paren")
+            .ignore(r"^\s+")
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token))
+            .token(r"^\)", |_, token| {
+                TokenFactory::new("close").pop_state(1).build(token)
+            });
+
+        let src = "if a:\n    b (c\n  d)\n\n  # note\n\tif e:\n        f\ng";
+        let tokens: Vec<_> = state
+            .tokens(src)
+            .map(|token| token.expect("error"))
+            .collect();
+        assert_eq!(
+            vec![
+                "name", "name", "colon", "newline", "indent", "name", "open", "name", "name",
+                "close", "newline", "name", "name", "colon", "newline", "indent", "name",
+                "newline", "dedent", "dedent", "name", "newline", "eof",
+            ],
+            tokens
+                .iter()
+                .map(|token| token.get_type())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("    ", tokens[4].get_value());
+        assert_eq!(
+            Location::new(Position::new(2, 0, 6), Position::new(2, 4, 10)),
+            tokens[4].get_location()
+        );
+        assert_eq!(
+            Location::new(Position::new(8, 0, 47), Position::new(8, 0, 47)),
+            tokens[18].get_location()
+        );
+
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^ +")
+            .set_indent(&IndentConfig::new("indent", "dedent", "newline"))
+            .state("init")
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token));
+
+        let src = "a\n    b\n  c";
+        assert_tokens(
+            &mut state,
+            src,
+            vec![
+                ("name", "a"),
+                ("newline", "\n"),
+                ("indent", "    "),
+                ("name", "b"),
+                ("newline", "\n"),
+            ],
+        );
+        assert_eq!(
+            Err(LexError::InconsistentDedent {
+                position: Position::new(3, 2, 10),
+                state: "init",
+                width: 2,
+            }),
+            state.next(src).map(|token| token.get_type())
+        );
+    }
+
+    #[test]
+    fn parse_state_changes() {
+        let new_state = || {
+            let mut state = LexerState::new("init", ());
+            state
+                .set_eof(|_, _| Token::new("eof", ""))
+                .set_ignore(r"^ ")
+                .state("init")
+                .token(r"^<", |_, token| {
+                    TokenFactory::new("open").push_state("inner").build(token)
+                })
+                .token(r"^!", |_, token| {
+                    TokenFactory::new("begin").begin_state("inner").build(token)
+                })
+                .token(r"^\)", |_, token| {
+                    TokenFactory::new("pop").pop_state(1).build(token)
+                })
+                .state("inner")
+                .token(r"^<", |_, token| {
+                    TokenFactory::new("open").push_state("inner").build(token)
+                })
+                .token(r"^>", |_, token| {
+                    TokenFactory::new("close").pop_to_state("init").build(token)
+                })
+                .token(r"^=", |_, token| {
+                    TokenFactory::new("replace")
+                        .replace_state("other")
+                        .build(token)
+                })
+                .state("other")
+                .token(r"^\.", |_, token| {
+                    TokenFactory::new("reset").reset_state().build(token)
+                });
+
+            state
+        };
+
+        let mut state = new_state();
+        let src = "< < < > < = . ! >";
+        let mut states = Vec::new();
+        while !state.is_eof() {
+            let token = state.next(src).expect("error");
+            states.push((token.get_type(), state.get_current_state()));
+        }
+        assert_eq!(
+            vec![
+                ("open", "inner"),
+                ("open", "inner"),
+                ("open", "inner"),
+                ("close", "init"),
+                ("open", "inner"),
+                ("replace", "other"),
+                ("reset", "init"),
+                ("begin", "inner"),
+                ("close", "inner"),
+                ("eof", "inner"),
+            ],
+            states
+        );
+
+        let src = ") )";
+        let mut state = new_state();
+        assert_tokens(
+            &mut state,
+            src,
+            vec![("pop", ")"), ("pop", ")"), ("eof", "")],
+        );
+
+        let mut state = new_state();
+        state.set_strict_states(true);
+        assert_eq!(
+            Err(LexError::StateStackUnderflow {
+                position: Position::new(1, 0, 0),
+                state: "init",
+                times: 1,
+            }),
+            state.next(src).map(|token| token.get_type())
+        );
+        // 状态改变失败时文本指针保持不变
+        assert_eq!(0, state.get_offset());
+
+        let src = "< = >";
+        let mut state = new_state();
+        state.set_strict_states(true);
+        state.state("other").token(r"^>", |_, token| {
+            TokenFactory::new("close")
+                .pop_to_state("missing")
+                .build(token)
+        });
+        assert_tokens(&mut state, src, vec![("open", "<"), ("replace", "=")]);
+        assert_eq!(
+            Err(LexError::MissingState {
+                position: Position::new(1, 4, 4),
+                state: "other",
+                target: "missing",
+            }),
+            state.next(src).map(|token| token.get_type())
+        );
+        assert_eq!(4, state.get_offset());
+
+        // 重置后仍然严格检查状态栈
+        state.reset("init", ());
+        state.state("init").token(r"^\)", |_, token| {
+            TokenFactory::new("pop").pop_state(1).build(token)
+        });
+        assert_eq!(
+            Err(LexError::StateStackUnderflow {
+                position: Position::new(1, 0, 0),
+                state: "init",
+                times: 1,
+            }),
+            state.next(")").map(|token| token.get_type())
+        );
+    }
+
+    #[test]
+    fn parse_dynamic_states() {
+        let names: Vec<String> = (0..3).map(|depth| format!("depth_{}", depth)).collect();
+
+        let mut state = LexerState::new(names[0].clone(), ());
+        state.set_eof(|_, _| Token::new("eof", ""));
+        for (depth, name) in names.iter().enumerate() {
+            let next = StateId::new(&names[(depth + 1) % names.len()]);
+            state.state(name.clone()).token(r"^\+", move |_, token| {
+                TokenFactory::new("push").push_state(next).build(token)
+            });
+        }
+
+        let src = "++++";
+        let mut states = Vec::new();
+        while !state.is_eof() {
+            state.next(src).expect("error");
+            states.push(state.get_current_state());
+        }
+        assert_eq!(
+            vec!["depth_1", "depth_2", "depth_0", "depth_1", "depth_1"],
+            states
+        );
+        assert_eq!(StateId::from("depth_1"), state.get_current_state_id());
+    }
+
+    #[test]
+    fn parse_trace() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let trace_events = events.clone();
+
+        let mut state = LexerState::new("trace_init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^ ")
+            .set_trace(move |event| trace_events.borrow_mut().push(event.to_string()))
+            .state("trace_init")
+            .token(r"^[a-z]+", |_, token| match token {
+                "if" => TokenFactory::new("if").reject(),
+                _ => TokenFactory::new("name").build(token),
+            })
+            .token(r"^if", |_, token| {
+                TokenFactory::new("if")
+                    .push_state("trace_inner")
+                    .build(token)
+            })
+            .state("trace_inner")
+            .token(r"^x", |_, token| {
+                TokenFactory::new("x").pop_state(1).build(token)
+            });
+
+        assert_eq!(
+            vec!["name", "if", "x", "eof"],
+            state
+                .tokens("ab if x")
+                .map(|token| token.expect("error").get_type())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                "1:0 <trace_init> try rule 0 /^[a-z]+/ on \"ab\"",
+                "1:0 <trace_init> match rule 0 (2 bytes)",
+                "1:2 <trace_init> ignore 1 bytes",
+                "1:3 <trace_init> try rule 0 /^[a-z]+/ on \"if\"",
+                "1:3 <trace_init> reject rule 0",
+                "1:3 <trace_init> try rule 1 /^if/ on \"if\"",
+                "1:3 <trace_init> match rule 1 (2 bytes)",
+                "1:5 <trace_init> state Push(StateId(\"trace_inner\")) -> <trace_inner>",
+                "1:5 <trace_inner> ignore 1 bytes",
+                "1:6 <trace_inner> try rule 0 /^x/ on \"x\"",
+                "1:6 <trace_inner> match rule 0 (1 bytes)",
+                "1:7 <trace_inner> state Pop(1) -> <trace_init>",
+                "1:7 <trace_init> eof",
+            ],
+            *events.borrow()
+        );
+
+        // 重置后不再调用调试回调
+        state.reset("trace_init", ());
+        assert!(state.next("?").is_err());
+        assert_eq!(13, events.borrow().len());
+
+        // 最长匹配时尝试全部规则，包括组合的正则表达式已经排除的规则
+        events.borrow_mut().clear();
+        let trace_events = events.clone();
+        let mut state = LexerState::new("trace_init", ());
+        state
+            .set_match_policy(MatchPolicy::LongestMatch)
+            .set_trace(move |event| trace_events.borrow_mut().push(event.to_string()))
+            .state("trace_init")
+            .token(r"^[0-9]+", |_, token| {
+                TokenFactory::new("number").build(token)
+            })
+            .token(r"^i", |_, token| TokenFactory::new("i").build(token))
+            .token(r"^if", |_, token| TokenFactory::new("if").build(token));
+
+        assert_eq!("if", state.next("if").expect("error").get_type());
+        assert_eq!(
+            vec![
+                "1:0 <trace_init> try rule 0 /^[0-9]+/, no match",
+                "1:0 <trace_init> try rule 1 /^i/ on \"i\"",
+                "1:0 <trace_init> try rule 2 /^if/ on \"if\"",
+                "1:0 <trace_init> match rule 2 (2 bytes)",
+            ],
+            *events.borrow()
+        );
+    }
+
+    #[test]
+    fn parse_recovery() {
+        let new_state = || {
+            let mut state = LexerState::new("init", ());
+            state
+                .set_eof(|_, _| Token::new("eof", ""))
+                .set_ignore(r"^ ")
+                .state("init")
+                .token(r"^[a-z]+", |_, token| {
+                    TokenFactory::new("name").build(token)
+                })
+                .token(r"^;", |_, token| TokenFactory::new("semi").build(token));
+
+            state
+        };
+        let collect = |state: &mut LexerState<()>, src: &str| {
+            state
+                .tokens(src)
+                .map(|token| {
+                    let token = token.expect("error");
+                    let location = token.get_location();
+                    (
+                        token.get_type(),
+                        token.get_value().to_string(),
+                        location.get_begin().get_offset(),
+                        location.get_end().get_offset(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let src = "ab 1é2 cd; ef";
+
+        let mut state = new_state();
+        assert!(state.next(src).is_ok());
+        assert!(matches!(state.next(src), Err(LexError::NoMatch { .. })));
+        assert!(matches!(state.next(src), Err(LexError::NoMatch { .. })));
+
+        let mut state = new_state();
+        state.set_recovery(RecoveryPolicy::SkipChar("error"));
+        assert_eq!(
+            vec![
+                ("name", String::from("ab"), 0, 2),
+                ("error", String::from("1"), 3, 4),
+                ("error", String::from("é"), 4, 6),
+                ("error", String::from("2"), 6, 7),
+                ("name", String::from("cd"), 8, 10),
+                ("semi", String::from(";"), 10, 11),
+                ("name", String::from("ef"), 12, 14),
+                ("eof", String::new(), 14, 14),
+            ],
+            collect(&mut state, src)
+        );
+
+        let mut state = new_state();
+        state.set_recovery_sync("error", "^;");
+        assert_eq!(
+            vec![
+                ("name", String::from("ab"), 0, 2),
+                ("error", String::from("1é2 cd"), 3, 10),
+                ("semi", String::from(";"), 10, 11),
+                ("name", String::from("ef"), 12, 14),
+                ("eof", String::new(), 14, 14),
+            ],
+            collect(&mut state, src)
+        );
+
+        let mut state = new_state();
+        state.set_recovery_sync("error", "^;");
+        assert_eq!(
+            vec![
+                ("error", String::from("1é2"), 0, 4),
+                ("eof", String::new(), 4, 4)
+            ],
+            collect(&mut state, "1é2")
+        );
+
+        // 重置后不再进行恢复
+        state.reset("init", ());
+        assert!(matches!(state.get_recovery(), RecoveryPolicy::Fail));
+    }
+
+    fn new_counter_state() -> LexerState<usize> {
+        let mut state = LexerState::new("init", 0);
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^ ")
+            .state("init")
+            .token(r"^\{", |depth, token| {
+                *depth += 1;
+                TokenFactory::new("open").push_state("inner").build(token)
+            })
+            .state("inner")
+            .token(r"^\}", |depth, token| {
+                *depth -= 1;
+                TokenFactory::new("close").pop_state(1).build(token)
+            })
+            .token(r"^\{", |depth, token| {
+                *depth += 1;
+                TokenFactory::new("open").push_state("inner").build(token)
+            });
+
+        state
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let src = "{ { } }";
+        let mut state = new_counter_state();
+        state.next(src).expect("error");
+
+        let snapshot = state.dump_with_state();
+        let copied = snapshot.clone();
+        assert_eq!(snapshot, copied);
+        assert_eq!(1, *snapshot.get_custom_state());
+
+        for _ in 0..2 {
+            state.restore_with_state(&copied);
+            let types: Vec<_> = state
+                .tokens(src)
+                .map(|token| token.expect("error").get_type())
+                .collect();
+            assert_eq!(vec!["open", "close", "close", "eof"], types);
+            assert_eq!(0, *state.get_custom_state());
+            assert_ne!(snapshot, state.dump_with_state());
+        }
+
+        // restore 不还原自定义状态
+        state.restore(&snapshot);
+        assert_eq!(
+            snapshot.get_token_snapshot(),
+            state.dump().get_token_snapshot()
+        );
+        assert_eq!(0, *state.get_custom_state());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_serde() {
+        let src = "{ { } }";
+        let mut state = new_counter_state();
+        state.next(src).expect("error");
+        state.next(src).expect("error");
+
+        let snapshot = state.dump_with_state();
+        let json = serde_json::to_string(&snapshot).expect("serialize");
+        let restored: LexerStateSnapshot<&'static str, usize> =
+            serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(snapshot, restored);
+
+        let mut state = new_counter_state();
+        state.restore_with_state(&restored);
+        assert_eq!(2, *state.get_custom_state());
+        assert_eq!(StateId::from("inner"), state.get_current_state_id());
+        assert_eq!("close", state.next(src).expect("error").get_type());
+
+        // 未登记的状态将被拒绝，不会登记新的名称
+        let json = json.replace("inner", "unregistered_inner");
+        assert!(serde_json::from_str::<LexerStateSnapshot<&'static str, usize>>(&json).is_err());
+        assert_eq!(None, StateId::lookup("unregistered_inner"));
+    }
 }
@@ -1,21 +1,31 @@
-use super::Position;
+use super::{Position, SourceId};
 use std::fmt::{Display, Formatter, Result};
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
+    source: SourceId,
     begin: Position,
     end: Position,
 }
 
 impl Location {
     pub fn new(begin: Position, end: Position) -> Self {
-        Location { begin, end }
+        Location::new_with_source(SourceId::UNKNOWN, begin, end)
+    }
+
+    pub fn new_with_source(source: SourceId, begin: Position, end: Position) -> Self {
+        Location { source, begin, end }
     }
 
     pub fn new_zero() -> Self {
         Location::new(Position::new_zero(), Position::new_zero())
     }
 
+    pub fn get_source(&self) -> SourceId {
+        self.source
+    }
+
     pub fn get_begin(&self) -> Position {
         self.begin
     }
@@ -23,10 +33,49 @@ impl Location {
     pub fn get_end(&self) -> Position {
         self.end
     }
+
+    // 同时移动开始和结束位置，参见 Position::shift
+    pub fn shift(&self, line: usize, lines: isize, columns: isize, offset: isize) -> Self {
+        Location::new_with_source(
+            self.source,
+            self.begin.shift(line, lines, columns, offset),
+            self.end.shift(line, lines, columns, offset),
+        )
+    }
+
+    // 从源码中截取该位置对应的文本
+    // src: 该位置所属的源码
+    pub fn get_text<'s>(&self, src: &'s str) -> Option<&'s str> {
+        src.get(self.begin.get_offset()..self.end.get_offset())
+    }
+
+    // 合并两个位置，得到同时覆盖两者的位置，不属于同一源码时返回 None
+    pub fn merge(&self, other: &Location) -> Option<Location> {
+        if self.source.ne(&other.source) {
+            return None;
+        }
+
+        let begin = if self.begin <= other.begin {
+            self.begin
+        } else {
+            other.begin
+        };
+        let end = if self.end >= other.end {
+            self.end
+        } else {
+            other.end
+        };
+
+        Some(Location::new_with_source(self.source, begin, end))
+    }
 }
 
 impl Display for Location {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "Location(begin: {}, end: {})", self.begin, self.end)
+        write!(
+            f,
+            "Location(source: {}, begin: {}, end: {})",
+            self.source, self.begin, self.end
+        )
     }
 }
@@ -0,0 +1,40 @@
+// 同一状态下有多个规则可以匹配时，选择规则的策略
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchPolicy {
+    // 按照注册顺序，选择第一个匹配的规则
+    #[default]
+    FirstMatch,
+    // 选择匹配最长的规则，长度相同时选择先注册的规则（与 flex 一致）
+    LongestMatch,
+    // 选择优先级最高的规则，优先级相同时按照 LongestMatch 选择
+    Priority,
+}
+
+impl MatchPolicy {
+    // 按照匹配策略从 (规则序号, 匹配长度) 中选择一个规则
+    // 只有严格更优时才替换，使得先注册的规则在平局时胜出
+    // priority: 规则序号对应的优先级
+    pub fn select<I, P>(self, matches: I, priority: P) -> Option<(usize, usize)>
+    where
+        I: Iterator<Item = (usize, usize)>,
+        P: Fn(usize) -> i32,
+    {
+        let mut selected: Option<(usize, usize)> = None;
+        for (index, len) in matches {
+            let better = match selected {
+                _ if self.eq(&MatchPolicy::FirstMatch) => return Some((index, len)),
+                None => true,
+                Some((best, best_len)) => match self {
+                    MatchPolicy::Priority => (priority(index), len) > (priority(best), best_len),
+                    _ => len > best_len,
+                },
+            };
+
+            if better {
+                selected = Some((index, len));
+            }
+        }
+
+        selected
+    }
+}
@@ -1,13 +1,75 @@
+mod byte_lexer_state;
+mod byte_token;
+mod clone_checkpoint;
+mod custom_checkpoint;
+#[cfg(feature = "generator")]
+mod generate_error;
+mod haystack;
+mod incremental;
+mod indent;
+mod interner;
+mod lex_error;
+#[cfg(feature = "generator")]
+mod lexer_generator;
+mod lexer_spec;
 mod lexer_state;
 mod location;
+mod match_policy;
+mod pattern_error;
 mod position;
+mod prefix_matcher;
+mod recovery_policy;
+#[cfg(feature = "serde")]
+mod serde_kind;
+mod source_map;
+mod spec_error;
 mod state_change;
+mod state_id;
+mod state_stack;
+mod state_stack_error;
+mod stream_lexer;
+mod text_edit;
 mod token;
+mod token_action;
 mod token_factory;
+mod token_kind;
+mod token_stream;
+mod trace_event;
+mod unicode;
 
+pub use byte_lexer_state::*;
+pub use byte_token::*;
+pub use clone_checkpoint::*;
+pub use custom_checkpoint::*;
+#[cfg(feature = "generator")]
+pub use generate_error::*;
+pub use haystack::*;
+pub use incremental::*;
+pub use indent::*;
+pub use interner::*;
+pub use lex_error::*;
+#[cfg(feature = "generator")]
+pub use lexer_generator::*;
+pub use lexer_spec::*;
 pub use lexer_state::*;
 pub use location::*;
+pub use match_policy::*;
+pub use pattern_error::*;
 pub use position::*;
+pub use prefix_matcher::*;
+pub use recovery_policy::*;
+pub use source_map::*;
+pub use spec_error::*;
 pub use state_change::*;
+pub use state_id::*;
+pub use state_stack::*;
+pub use state_stack_error::*;
+pub use stream_lexer::*;
+pub use text_edit::*;
 pub use token::*;
+pub use token_action::*;
 pub use token_factory::*;
+pub use token_kind::*;
+pub use token_stream::*;
+pub use trace_event::*;
+pub use unicode::*;
@@ -0,0 +1,130 @@
+use regex::{bytes, Regex};
+use regex_syntax::hir::Look;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatternErrorKind {
+    // 正则表达式无法编译
+    InvalidRegex(regex::Error),
+    // 正则表达式没有以 ^ 锚定在未解析源码的开头
+    Unanchored,
+}
+
+// 注册规则时被拒绝的正则表达式
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternError {
+    state: Option<&'static str>,
+    pattern: String,
+    kind: PatternErrorKind,
+}
+
+impl PatternError {
+    pub fn new(state: Option<&'static str>, pattern: &str, kind: PatternErrorKind) -> Self {
+        PatternError {
+            state,
+            pattern: String::from(pattern),
+            kind,
+        }
+    }
+
+    // 编译规则的正则表达式，并检查其是否锚定在源码开头
+    // state: 规则所属的状态，全局的忽略字符和注释为 None
+    // re: 正则表达式
+    pub fn compile(state: Option<&'static str>, re: &str) -> std::result::Result<Regex, Self> {
+        let regex = Regex::new(re)
+            .map_err(|err| PatternError::new(state, re, PatternErrorKind::InvalidRegex(err)))?;
+
+        Self::anchored(state, re).map(|_| regex)
+    }
+
+    // 编译 ByteLexerState 使用的正则表达式，可以通过 (?-u) 匹配任意字节
+    pub fn compile_bytes(
+        state: Option<&'static str>,
+        re: &str,
+    ) -> std::result::Result<bytes::Regex, Self> {
+        let regex = bytes::Regex::new(re)
+            .map_err(|err| PatternError::new(state, re, PatternErrorKind::InvalidRegex(err)))?;
+
+        Self::anchored(state, re).map(|_| regex)
+    }
+
+    // 检查正则表达式是否锚定在源码开头
+    fn anchored(state: Option<&'static str>, re: &str) -> std::result::Result<(), Self> {
+        let anchored = regex_syntax::ParserBuilder::new()
+            .utf8(false)
+            .build()
+            .parse(re)
+            .map(|hir| hir.properties().look_set_prefix().contains(Look::Start))
+            .unwrap_or(false);
+        if anchored {
+            Ok(())
+        } else {
+            Err(PatternError::new(state, re, PatternErrorKind::Unanchored))
+        }
+    }
+
+    pub fn get_state(&self) -> Option<&'static str> {
+        self.state
+    }
+
+    pub fn get_pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    pub fn get_kind(&self) -> &PatternErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.state {
+            Some(state) => write!(f, "state `{}`, pattern {:?}: ", state, self.pattern)?,
+            None => write!(f, "ignore pattern {:?}: ", self.pattern)?,
+        }
+
+        match &self.kind {
+            PatternErrorKind::InvalidRegex(err) => write!(f, "{}", err),
+            PatternErrorKind::Unanchored => write!(f, "pattern must be anchored with `^`"),
+        }
+    }
+}
+
+impl Error for PatternError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            PatternErrorKind::InvalidRegex(err) => Some(err),
+            PatternErrorKind::Unanchored => None,
+        }
+    }
+}
+
+// 构造词法解析器时收集到的所有错误
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexerBuildError {
+    errors: Vec<PatternError>,
+}
+
+impl LexerBuildError {
+    pub fn new(errors: Vec<PatternError>) -> Self {
+        LexerBuildError { errors }
+    }
+
+    pub fn get_errors(&self) -> &[PatternError] {
+        self.errors.as_slice()
+    }
+}
+
+impl Display for LexerBuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{} invalid lexer pattern(s)", self.errors.len())?;
+        for error in &self.errors {
+            write!(f, "\n  {}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for LexerBuildError {}
@@ -1,19 +1,25 @@
 use std::cmp::Ordering;
 use std::fmt::{Display, Formatter, Result};
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     line: usize,
     column: usize,
+    offset: usize,
 }
 
 impl Position {
-    pub fn new(line: usize, column: usize) -> Self {
-        Position { line, column }
+    pub fn new(line: usize, column: usize, offset: usize) -> Self {
+        Position {
+            line,
+            column,
+            offset,
+        }
     }
 
     pub fn new_zero() -> Self {
-        Position::new(0, 0)
+        Position::new(0, 0, 0)
     }
 
     pub fn get_line(&self) -> usize {
@@ -23,23 +29,40 @@ impl Position {
     pub fn get_column(&self) -> usize {
         self.column
     }
+
+    // 相对源码开头的字节偏移量
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    // 移动位置，用于源码修改后调整修改处之后的位置
+    // line: 修改前与修改处位于同一行的行号，只有该行的位置需要调整列号
+    // lines: 增加的行数
+    // columns: 第 line 行的位置增加的列数
+    // offset: 增加的字节数
+    pub fn shift(&self, line: usize, lines: isize, columns: isize, offset: isize) -> Self {
+        let columns = if self.line.eq(&line) { columns } else { 0 };
+
+        Position::new(
+            self.line.saturating_add_signed(lines),
+            self.column.saturating_add_signed(columns),
+            self.offset.saturating_add_signed(offset),
+        )
+    }
 }
 
 impl PartialOrd for Position {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.line < other.line || (self.line == other.line && self.column < other.column) {
-            Some(Ordering::Less)
-        } else if self.line > other.line || (self.line == other.line && self.column > other.column)
-        {
-            Some(Ordering::Greater)
-        } else {
-            Some(Ordering::Equal)
-        }
+        Some((self.line, self.column, self.offset).cmp(&(other.line, other.column, other.offset)))
     }
 }
 
 impl Display for Position {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "Position(line: {}, column: {})", self.line, self.column)
+        write!(
+            f,
+            "Position(line: {}, column: {}, offset: {})",
+            self.line, self.column, self.offset
+        )
     }
 }
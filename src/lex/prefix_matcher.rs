@@ -0,0 +1,56 @@
+use regex_automata::hybrid::dfa::{Cache, DFA};
+use regex_automata::util::syntax;
+use regex_automata::{Anchored, Input};
+
+// 判断一组锚定的正则表达式在读入源码之后是否仍可能继续匹配
+//
+// 用于源码只有一部分时（参见 LexerState::set_partial）判断一个 Token
+// 是否可能越过已读入部分的末尾。使用惰性构造的 DFA 逐字节运行，
+// 所有规则都无法继续时立即停止。
+pub struct PrefixMatcher {
+    dfa: DFA,
+    cache: Cache,
+}
+
+impl PrefixMatcher {
+    // 正则表达式为空或无法构造 DFA（如使用了 Unicode 单词边界）时返回 None
+    // utf8: 正则表达式是否只匹配合法的 UTF-8，参见 Haystack::UTF8
+    pub fn new(patterns: &[&str], utf8: bool) -> Option<Self> {
+        if patterns.is_empty() {
+            return None;
+        }
+
+        let dfa = DFA::builder()
+            .syntax(syntax::Config::new().utf8(utf8))
+            .build_many(patterns)
+            .ok()?;
+        let cache = dfa.create_cache();
+
+        Some(PrefixMatcher { dfa, cache })
+    }
+
+    // 读入 src 之后是否仍有正则表达式可能匹配更长的文本
+    // 无法确定时返回 true
+    pub fn is_live(&mut self, src: &[u8]) -> bool {
+        let input = Input::new(src).anchored(Anchored::Yes);
+        let mut state = match self.dfa.start_state_forward(&mut self.cache, &input) {
+            Ok(state) => state,
+            Err(_) => return true,
+        };
+
+        for &byte in src {
+            state = match self.dfa.next_state(&mut self.cache, state, byte) {
+                Ok(state) => state,
+                Err(_) => return true,
+            };
+            if state.is_dead() {
+                return false;
+            }
+            if state.is_quit() {
+                return true;
+            }
+        }
+
+        true
+    }
+}
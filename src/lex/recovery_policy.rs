@@ -0,0 +1,52 @@
+use super::{Haystack, TokenKind};
+use regex::Regex;
+
+// 没有任何规则能够匹配时的恢复策略
+//
+// 恢复时跳过的文本将作为错误 Token 返回，Token 的位置即为跳过的文本所在的位置，
+// 之后的解析从跳过的文本之后继续进行。R 为同步模式的正则表达式，
+// ByteLexerState 使用 regex::bytes::Regex，并按字节跳过。
+#[derive(Clone, Debug)]
+pub enum RecoveryPolicy<K, R = Regex> {
+    // 不进行恢复，返回 LexError::NoMatch
+    Fail,
+    // 跳过一个字符，产生类型为 K 的错误 Token
+    SkipChar(K),
+    // 至少跳过一个字符，直到同步模式能够匹配或者源码结束，产生类型为 K 的错误 Token
+    // 同步模式与规则一样以 ^ 开头，从跳过的第一个字符之后逐个字符尝试匹配
+    Sync(K, R),
+}
+
+impl<K: TokenKind, R> RecoveryPolicy<K, R> {
+    // 错误 Token 的类型，不进行恢复时为 None
+    pub fn get_kind(&self) -> Option<K> {
+        match self {
+            RecoveryPolicy::Fail => None,
+            RecoveryPolicy::SkipChar(kind) | RecoveryPolicy::Sync(kind, _) => Some(*kind),
+        }
+    }
+
+    // 计算需要跳过的字节数，不进行恢复时为 None
+    // src: 无法匹配的源码
+    pub fn skip_len<H>(&self, src: &H) -> Option<usize>
+    where
+        H: ?Sized + Haystack<Regex = R>,
+    {
+        let len = src.as_bytes().len();
+        let first = (1..=len).find(|index| src.is_char_boundary(*index))?;
+
+        match self {
+            RecoveryPolicy::Fail => None,
+            RecoveryPolicy::SkipChar(_) => Some(first),
+            RecoveryPolicy::Sync(_, sync) => Some(
+                (first..len)
+                    .filter(|index| src.is_char_boundary(*index))
+                    .find(|index| {
+                        src.get_from(*index)
+                            .is_some_and(|rest| H::is_match(sync, rest))
+                    })
+                    .unwrap_or(len),
+            ),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+// 以名称序列化 Token 类型，供 #[serde(with = "...")] 使用
+use super::{Interner, TokenKind};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+pub fn serialize<K, S>(kind: &K, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: TokenKind,
+    S: Serializer,
+{
+    // 登记名称，使 &'static str 类型的 Token 类型可以被反序列化
+    Interner::intern_static(kind.name());
+
+    serializer.serialize_str(kind.name())
+}
+
+pub fn deserialize<'de, K, D>(deserializer: D) -> Result<K, D::Error>
+where
+    K: TokenKind,
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+
+    K::from_name(name.as_str())
+        .ok_or_else(|| D::Error::custom(format!("unknown token type `{}`", name)))
+}
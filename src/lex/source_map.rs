@@ -0,0 +1,123 @@
+use super::Location;
+use std::fmt::{Display, Formatter, Result};
+
+// 源码标识，由 SourceMap 从 1 开始分配
+// 0 保留为未知源码，是没有通过 set_source 设定源码的词法解析器和 Location::new 使用的默认值
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceId(usize);
+
+impl SourceId {
+    pub const UNKNOWN: SourceId = SourceId(0);
+
+    pub fn new(id: usize) -> Self {
+        SourceId(id)
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        self.eq(&SourceId::UNKNOWN)
+    }
+
+    pub fn get_id(&self) -> usize {
+        self.0
+    }
+}
+
+impl Display for SourceId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// 记录所有参与词法解析的源码，用于由 Location 找回源码文本
+pub struct SourceMap {
+    sources: Vec<(String, String)>,
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        SourceMap::new()
+    }
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap {
+            sources: Vec::new(),
+        }
+    }
+
+    // 注册一份源码
+    // name: 源码名称，如文件路径
+    // src: 源码
+    pub fn add(&mut self, name: &str, src: &str) -> SourceId {
+        self.sources.push((String::from(name), String::from(src)));
+
+        SourceId::new(self.sources.len())
+    }
+
+    // 未知源码和未注册的源码返回 None
+    fn get(&self, source: SourceId) -> Option<&(String, String)> {
+        source
+            .get_id()
+            .checked_sub(1)
+            .and_then(|index| self.sources.get(index))
+    }
+
+    pub fn get_name(&self, source: SourceId) -> Option<&str> {
+        self.get(source).map(|(name, _)| name.as_str())
+    }
+
+    pub fn get_source(&self, source: SourceId) -> Option<&str> {
+        self.get(source).map(|(_, src)| src.as_str())
+    }
+
+    // 获取 location 对应的源码文本
+    pub fn get_text(&self, location: &Location) -> Option<&str> {
+        self.get_source(location.get_source())
+            .and_then(|src| location.get_text(src))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{LexerState, Position, Token, TokenFactory};
+    use super::*;
+
+    #[test]
+    fn source_text() {
+        let mut source_map = SourceMap::new();
+        let first = source_map.add("first.txt", "foo bar");
+        let second = source_map.add("second.txt", "baz\n  qux");
+
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^\s+")
+            .state("init")
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token));
+
+        state.set_source(second);
+        let src = source_map.get_source(second).unwrap_or("");
+        let baz = state.next(src).expect("error").get_location();
+        let qux = state.next(src).expect("error").get_location();
+
+        assert_eq!(Some("second.txt"), source_map.get_name(baz.get_source()));
+        assert_eq!(Some("baz"), source_map.get_text(&baz));
+        assert_eq!(Some("qux"), source_map.get_text(&qux));
+        assert_eq!(Position::new(2, 2, 6), qux.get_begin());
+
+        let span = qux.merge(&baz).expect("error");
+        assert_eq!(Some("baz\n  qux"), source_map.get_text(&span));
+
+        let foo = Location::new_with_source(first, Position::new(1, 0, 0), Position::new(1, 3, 3));
+        assert_eq!(Some("foo"), source_map.get_text(&foo));
+        assert_eq!(None, foo.merge(&baz));
+
+        // 没有设定源码的 Location 不属于任何已注册的源码
+        let unknown = Location::new(Position::new(1, 0, 0), Position::new(1, 3, 3));
+        assert!(unknown.get_source().is_unknown());
+        assert_eq!(None, source_map.get_text(&unknown));
+        assert_eq!(None, unknown.merge(&foo));
+    }
+}
@@ -0,0 +1,33 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result};
+
+// 词法规格文件中无法解析的行
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LexerSpecError {
+    line: usize,
+    message: String,
+}
+
+impl LexerSpecError {
+    // line: 出错的行号，从 1 开始
+    // message: 错误信息
+    pub fn new(line: usize, message: String) -> Self {
+        LexerSpecError { line, message }
+    }
+
+    pub fn get_line(&self) -> usize {
+        self.line
+    }
+
+    pub fn get_message(&self) -> &str {
+        self.message.as_str()
+    }
+}
+
+impl Display for LexerSpecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for LexerSpecError {}
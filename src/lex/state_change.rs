@@ -1,7 +1,19 @@
-#[derive(Clone, Copy)]
+use super::StateId;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum NextStateChange {
-    Push(&'static str),
+    // 将当前状态压入状态栈，并切换到新的状态
+    Push(StateId),
+    // 从状态栈中弹出若干个状态
     Pop(usize),
+    // 替换当前状态，状态栈保持不变
+    Replace(StateId),
+    // 弹出状态直到当前状态为指定的状态
+    PopTo(StateId),
+    // 清空状态栈并回到初始状态
+    Reset,
+    // 清空状态栈并切换到指定的状态，类似于 flex 的 BEGIN
+    Begin(StateId),
 }
 
 pub struct StateChange {
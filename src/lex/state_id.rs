@@ -0,0 +1,101 @@
+use super::Interner;
+use std::fmt::{Debug, Display, Formatter, Result};
+
+// 词法解析器状态的标识
+//
+// 状态名称在首次使用时登记到全局的名称表（参见 Interner）中，之后以
+// 序号表示，因此相同名称得到的 StateId 总是相等。
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StateId(u32);
+
+impl StateId {
+    // 获取名称对应的状态标识，名称尚未登记时复制并登记该名称
+    pub fn new(name: &str) -> Self {
+        StateId(Interner::intern(name))
+    }
+
+    // 获取已经登记的名称对应的状态标识，不会登记新的名称
+    pub fn lookup(name: &str) -> Option<Self> {
+        Interner::lookup(name).map(StateId)
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.0
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        Interner::get_name(self.0)
+    }
+}
+
+impl From<&'static str> for StateId {
+    fn from(name: &'static str) -> Self {
+        StateId(Interner::intern_static(name))
+    }
+}
+
+impl From<String> for StateId {
+    fn from(name: String) -> Self {
+        Self::new(name.as_str())
+    }
+}
+
+impl PartialEq<&str> for StateId {
+    fn eq(&self, other: &&str) -> bool {
+        self.get_name().eq(*other)
+    }
+}
+
+impl Debug for StateId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "StateId({:?})", self.get_name())
+    }
+}
+
+impl Display for StateId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.get_name())
+    }
+}
+
+// 序号只在当前进程内有效，因此以名称序列化
+// 反序列化时只接受已经登记的状态，即需要先构造注册了这些状态的词法解析器，
+// 避免不可信的输入不断登记新的名称
+#[cfg(feature = "serde")]
+impl serde::Serialize for StateId {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.get_name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for StateId {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+
+        StateId::lookup(name.as_str())
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown state `{}`", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interned_state() {
+        let state = StateId::from("interned_state");
+        let name = String::from("interned_") + "state";
+
+        assert_eq!(state, StateId::from(name.clone()));
+        assert_eq!(state, StateId::new(&name));
+        assert_ne!(state, StateId::from("other_state"));
+        assert_eq!("interned_state", state.get_name());
+        assert!(state == "interned_state");
+    }
+}
@@ -0,0 +1,155 @@
+use super::{LexerTokenSnapshot, NextStateChange, StateId, StateStackError};
+
+// 词法解析器的状态栈，由 LexerState 和 ByteLexerState 共用
+#[derive(Clone, Debug)]
+pub struct StateStack {
+    state_stack: Vec<StateId>,
+    current_state: StateId,
+    initial_state: StateId,
+    strict: bool,
+}
+
+impl StateStack {
+    pub fn new(initial_status: impl Into<StateId>) -> Self {
+        let initial_status = initial_status.into();
+
+        StateStack {
+            state_stack: Vec::new(),
+            current_state: initial_status,
+            initial_state: initial_status,
+            strict: false,
+        }
+    }
+
+    pub fn reset(&mut self, initial_status: impl Into<StateId>) {
+        let initial_status = initial_status.into();
+
+        self.state_stack.clear();
+        self.current_state = initial_status;
+        self.initial_state = initial_status;
+    }
+
+    pub fn get_current_state(&self) -> &'static str {
+        self.current_state.get_name()
+    }
+
+    pub fn get_current_state_id(&self) -> StateId {
+        self.current_state
+    }
+
+    // 状态栈中的状态数量
+    pub fn get_depth(&self) -> usize {
+        self.state_stack.len()
+    }
+
+    fn push(&mut self, state: StateId) {
+        self.state_stack.push(self.current_state);
+        self.current_state = state;
+    }
+
+    // 弹出 times 个状态，宽松模式下状态不足时弹出全部状态
+    fn pop(&mut self, times: usize) -> Result<(), StateStackError> {
+        if self.strict && times > self.state_stack.len() {
+            return Err(StateStackError::Underflow { times });
+        }
+
+        for _ in 0..times {
+            match self.state_stack.pop() {
+                Some(state) => self.current_state = state,
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    // 弹出状态直到当前状态为 state，宽松模式下栈中没有该状态时保持不变
+    fn pop_to(&mut self, state: StateId) -> Result<(), StateStackError> {
+        if self.current_state.eq(&state) {
+            return Ok(());
+        }
+
+        match self
+            .state_stack
+            .iter()
+            .rposition(|stacked| stacked.eq(&state))
+        {
+            Some(index) => {
+                self.state_stack.truncate(index);
+                self.current_state = state;
+
+                Ok(())
+            }
+            None if self.strict => Err(StateStackError::MissingState { target: state }),
+            None => Ok(()),
+        }
+    }
+
+    // 清空状态栈并切换到 state
+    fn begin(&mut self, state: StateId) {
+        self.state_stack.clear();
+        self.current_state = state;
+    }
+
+    // 设定是否在状态栈中没有足够的状态可以弹出时返回错误（默认不返回）
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn get_initial_state(&self) -> StateId {
+        self.initial_state
+    }
+
+    // 改变当前状态，严格模式下状态栈无法完成状态改变时返回错误
+    pub fn change_state(&mut self, next_state: &NextStateChange) -> Result<(), StateStackError> {
+        match *next_state {
+            NextStateChange::Push(state) => self.push(state),
+            NextStateChange::Pop(times) => return self.pop(times),
+            NextStateChange::Replace(state) => self.current_state = state,
+            NextStateChange::PopTo(state) => return self.pop_to(state),
+            NextStateChange::Reset => self.begin(self.initial_state),
+            NextStateChange::Begin(state) => self.begin(state),
+        }
+
+        Ok(())
+    }
+
+    // 依次执行所有状态改变，任何一个状态改变失败时状态保持不变
+    // 全部成功时返回每个状态改变及改变前后的当前状态
+    pub fn change_states(
+        &mut self,
+        next_state: &[NextStateChange],
+    ) -> Result<Vec<(NextStateChange, StateId, StateId)>, StateStackError> {
+        if next_state.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let snapshot = self.dump();
+        let mut changes = Vec::with_capacity(next_state.len());
+        for state in next_state {
+            let from = self.current_state;
+            if let Err(error) = self.change_state(state) {
+                self.restore(&snapshot);
+
+                return Err(error);
+            }
+
+            changes.push((*state, from, self.current_state));
+        }
+
+        Ok(changes)
+    }
+
+    pub fn dump(&self) -> LexerTokenSnapshot {
+        LexerTokenSnapshot::new(self.state_stack.clone(), self.current_state)
+    }
+
+    pub fn restore(&mut self, snapshot: &LexerTokenSnapshot) {
+        self.state_stack = snapshot.get_state_stack();
+        self.current_state = snapshot.get_current_state();
+    }
+}
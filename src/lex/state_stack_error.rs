@@ -0,0 +1,25 @@
+use super::StateId;
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result};
+
+// 严格模式下状态栈无法完成状态改变时的错误
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateStackError {
+    // 状态栈中没有足够的状态可以弹出
+    Underflow { times: usize },
+    // 当前状态和状态栈中都没有要弹出到的状态
+    MissingState { target: StateId },
+}
+
+impl Display for StateStackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Underflow { times } => write!(f, "cannot pop {} state(s)", times),
+            Self::MissingState { target } => {
+                write!(f, "state `{}` is not on the state stack", target)
+            }
+        }
+    }
+}
+
+impl Error for StateStackError {}
@@ -0,0 +1,377 @@
+/*
+ * 流式词法解析器
+ *
+ * StreamLexer 从任意 Read 中分块读取源码，并交给 LexerState
+ * 解析，已经解析完成的源码将被丢弃，因此可以解析无法一次性
+ * 读入内存的大文件。
+ *
+ * 正则表达式只能看到缓冲区中的内容，当一个 Token（或被忽略
+ * 的字符）恰好匹配到缓冲区末尾，或者当前状态下有规则在缓冲区
+ * 末尾仍可能继续匹配（参见 LexerState::set_partial）时，它可能
+ * 还会继续延伸，此时会还原 LexerState，读入更多内容后重新解析。因此位于缓冲区
+ * 边界处的 Token 的构造方法可能被调用多次。next_token 不会还原自定义
+ * 状态，需要还原时使用 next_token_checkpoint。
+ *
+ */
+
+use super::{CustomCheckpoint, LexError, LexerState, OwnedToken, Token, TokenKind};
+use std::io::{Error, ErrorKind, Read};
+use std::str;
+
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+const DEFAULT_MAX_TOKEN_LEN: usize = 16 * 1024 * 1024;
+
+pub struct StreamLexer<S, R, K = &'static str> {
+    lexer: LexerState<S, K>,
+    reader: R,
+
+    buffer: String,
+    // 末尾不完整的 UTF-8 字符
+    incomplete: Vec<u8>,
+    exhausted: bool,
+    read_error: Option<Error>,
+
+    chunk_size: usize,
+    max_token_len: usize,
+}
+
+impl<S, R: Read, K: TokenKind> StreamLexer<S, R, K> {
+    // 构造一个流式词法解析器
+    // lexer: 已经设定好规则的词法解析器
+    // reader: 源码
+    pub fn new(lexer: LexerState<S, K>, reader: R) -> Self {
+        StreamLexer {
+            lexer,
+            reader,
+
+            buffer: String::new(),
+            incomplete: Vec::new(),
+            exhausted: false,
+            read_error: None,
+
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            max_token_len: DEFAULT_MAX_TOKEN_LEN,
+        }
+    }
+
+    // 设定每次读取的字节数，同时也是解析每个 Token 前缓冲区中至少保留的未解析字节数
+    pub fn set_chunk_size(&mut self, chunk_size: usize) -> &mut Self {
+        self.chunk_size = chunk_size.max(1);
+
+        self
+    }
+
+    // 设定单个 Token 的最大字节数，超过该长度仍无法确定匹配结果时返回错误
+    pub fn set_max_token_len(&mut self, max_token_len: usize) -> &mut Self {
+        self.max_token_len = max_token_len;
+
+        self
+    }
+
+    pub fn get_lexer(&self) -> &LexerState<S, K> {
+        &self.lexer
+    }
+
+    pub fn get_mut_lexer(&mut self) -> &mut LexerState<S, K> {
+        &mut self.lexer
+    }
+
+    pub fn into_inner(self) -> (LexerState<S, K>, R) {
+        (self.lexer, self.reader)
+    }
+
+    fn io_error(&self, err: Error) -> LexError {
+        LexError::Io {
+            position: self.lexer.get_current_position(),
+            state: self.lexer.get_current_state(),
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+
+    // 读取一块源码，追加到缓冲区末尾
+    // 读取失败时停止读取，错误将在缓冲区中的内容解析完毕后返回
+    fn read_chunk(&mut self) {
+        let mut chunk = vec![0; self.chunk_size];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(read) => break read,
+                Err(err) if err.kind().eq(&ErrorKind::Interrupted) => continue,
+                Err(err) => {
+                    self.exhausted = true;
+                    self.read_error = Some(err);
+
+                    return;
+                }
+            }
+        };
+
+        if read.eq(&0) {
+            self.exhausted = true;
+            if !self.incomplete.is_empty() {
+                self.read_error = Some(Error::new(
+                    ErrorKind::InvalidData,
+                    "stream did not end on a UTF-8 character boundary",
+                ));
+            }
+
+            return;
+        }
+
+        self.incomplete.extend_from_slice(&chunk[..read]);
+        let valid_len = match str::from_utf8(&self.incomplete) {
+            Ok(valid) => valid.len(),
+            Err(err) => {
+                // 末尾的字符还没有读取完整时，等待下次读取
+                if err.error_len().is_some() {
+                    self.exhausted = true;
+                    self.read_error = Some(Error::new(ErrorKind::InvalidData, err));
+                }
+
+                err.valid_up_to()
+            }
+        };
+
+        let rest = self.incomplete.split_off(valid_len);
+        if let Ok(valid) = str::from_utf8(&self.incomplete) {
+            self.buffer.push_str(valid);
+        }
+        self.incomplete = rest;
+    }
+
+    // 读取源码，直到未解析的部分不少于 len 个字节或源码结束
+    fn fill(&mut self, len: usize) {
+        let parsed = self.lexer.get_offset();
+
+        while !self.exhausted && self.buffer.len() < parsed + len {
+            self.read_chunk();
+        }
+    }
+
+    // 丢弃缓冲区中已经解析完成的部分
+    // 偏移量（可能由 TokenFactory::offset 设定）不在字符边界上或超出缓冲区时返回错误
+    fn compact(&mut self, parsed: usize) -> Result<(), LexError> {
+        let parsed_src = match self.buffer.get(..parsed) {
+            Some(parsed_src) => parsed_src,
+            None => {
+                return Err(LexError::InvalidUtf8Boundary {
+                    position: self.lexer.get_current_position(),
+                    state: self.lexer.get_current_state(),
+                    offset: self.lexer.get_current_position().get_offset(),
+                })
+            }
+        };
+
+        // 保留 \r，使得跨越边界的 \r\n 仍然只计为一行
+        let len = if parsed_src.ends_with('\r') {
+            parsed - 1
+        } else {
+            parsed
+        };
+
+        if len.ge(&self.chunk_size) {
+            self.buffer.drain(..len);
+            self.lexer.discard(len);
+        }
+
+        Ok(())
+    }
+
+    // 从流中获取一个Token
+    pub fn next_token(&mut self) -> Result<OwnedToken<K>, LexError> {
+        self.next_token_with(LexerState::dump, LexerState::restore)
+    }
+
+    // 从流中获取一个Token，重新解析前通过 CustomCheckpoint 还原自定义状态
+    pub fn next_token_checkpoint(&mut self) -> Result<OwnedToken<K>, LexError>
+    where
+        S: CustomCheckpoint,
+    {
+        self.next_token_with(LexerState::dump_checkpoint, LexerState::restore_checkpoint)
+    }
+
+    // dump: 保存词法解析器状态的方法
+    // restore: 还原词法解析器状态的方法
+    fn next_token_with<T>(
+        &mut self,
+        dump: fn(&LexerState<S, K>) -> T,
+        restore: fn(&mut LexerState<S, K>, &T),
+    ) -> Result<OwnedToken<K>, LexError> {
+        let mut lookahead = self.chunk_size;
+
+        loop {
+            self.fill(lookahead);
+
+            let snapshot = dump(&self.lexer);
+            self.lexer.set_partial(!self.exhausted);
+            // 缓冲区会被改写，因此 Token 不能借用缓冲区
+            let result = self.lexer.next(&self.buffer).map(Token::into_owned);
+            let parsed = self.lexer.get_offset();
+
+            // 匹配到了缓冲区的末尾，或者有规则在缓冲区末尾仍可能继续匹配，
+            // 读入更多内容后重试。没有规则能够匹配更长的内容时返回 NoMatch，不再重试
+            let incomplete = match &result {
+                Ok(_) => parsed.ge(&self.buffer.len()),
+                Err(LexError::Incomplete { .. }) => true,
+                Err(_) => false,
+            };
+
+            if incomplete {
+                if let Some(err) = self.read_error.take() {
+                    restore(&mut self.lexer, &snapshot);
+
+                    break Err(self.io_error(err));
+                }
+            }
+
+            if !incomplete || self.exhausted || lookahead.ge(&self.max_token_len) {
+                self.lexer.set_partial(false);
+                if let Err(err) = self.compact(parsed) {
+                    break Err(err);
+                }
+
+                break result;
+            }
+
+            restore(&mut self.lexer, &snapshot);
+            lookahead = (lookahead * 2).min(self.max_token_len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::TokenFactory;
+    use super::*;
+
+    // 每次最多读取 3 个字节
+    struct SlowReader<'a>(&'a [u8]);
+
+    impl<'a> Read for SlowReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+            let len = buf.len().min(self.0.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+
+            Ok(len)
+        }
+    }
+
+    fn new_lexer_state() -> LexerState<()> {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^\s+")
+            .state("init")
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token))
+            .token(r#"^"[^"]*""#, |_, token| {
+                TokenFactory::new("string").build(token)
+            })
+            .token(r"^(==|=)", |_, token| {
+                TokenFactory::new("literal").build(token)
+            });
+        state
+    }
+
+    #[test]
+    fn stream_tokens() {
+        let src = "名字 == \"长  字符串\"\r\nvalue\r\n\r\n = other_name  ";
+
+        let mut state = new_lexer_state();
+        let mut expected = Vec::new();
+        loop {
+            let token = state.next(src).expect("error");
+            expected.push(token.clone());
+            if state.is_eof() {
+                break;
+            }
+        }
+
+        let mut stream = StreamLexer::new(new_lexer_state(), SlowReader(src.as_bytes()));
+        stream.set_chunk_size(2);
+        for expected_token in expected {
+            let token = stream.next_token().expect("error");
+            assert_eq!(expected_token.get_type(), token.get_type());
+            assert_eq!(expected_token.get_value(), token.get_value());
+            assert_eq!(expected_token.get_location(), token.get_location());
+        }
+        assert!(stream.get_lexer().is_eof());
+    }
+
+    #[test]
+    fn stream_competing_rules() {
+        let new_state = || {
+            let mut state = LexerState::new("init", ());
+            state
+                .set_eof(|_, _| Token::new("eof", ""))
+                .set_ignore(r"^\s+")
+                .state("init")
+                .token(r"^\w+", |_, token| TokenFactory::new("name").build(token))
+                .token(r#"^"[^"]*""#, |_, token| {
+                    TokenFactory::new("string").build(token)
+                })
+                .token(r#"^""#, |_, token| {
+                    TokenFactory::new("stray_quote").build(token)
+                });
+            state
+        };
+
+        // 较短的规则在缓冲区内匹配时，跨越缓冲区边界的较长 Token 不能被截断
+        let src = "a \"long string\" b";
+        let mut stream = StreamLexer::new(new_state(), SlowReader(src.as_bytes()));
+        stream.set_chunk_size(2);
+        for (token_type, token_value) in [
+            ("name", "a"),
+            ("string", "\"long string\""),
+            ("name", "b"),
+            ("eof", ""),
+        ] {
+            let token = stream.next_token().expect("error");
+            assert_eq!(token_type, token.get_type());
+            assert_eq!(token_value, token.get_value());
+        }
+    }
+
+    #[test]
+    fn stream_no_match() {
+        let src = format!("a ?{}", "b".repeat(1024));
+
+        // 没有规则能够匹配时立即返回错误，不继续读入源码
+        let mut stream = StreamLexer::new(new_lexer_state(), SlowReader(src.as_bytes()));
+        stream.set_chunk_size(2);
+        assert_eq!("a", stream.next_token().expect("error").get_value());
+        match stream.next_token() {
+            Err(LexError::NoMatch { position, .. }) => assert_eq!(2, position.get_offset()),
+            _ => panic!("error"),
+        }
+        assert!(stream.into_inner().1 .0.len() > 1000);
+    }
+
+    #[test]
+    fn stream_offset_boundary() {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .state("init")
+            .token(r"^.", |_, token| {
+                TokenFactory::new("char").offset(1).build(token)
+            });
+
+        // 偏移量落在多字节字符的中间时返回错误而不是崩溃
+        let mut stream = StreamLexer::new(state, SlowReader("字".as_bytes()));
+        match stream.next_token() {
+            Err(LexError::InvalidUtf8Boundary { offset, .. }) => assert_eq!(1, offset),
+            _ => panic!("error"),
+        }
+    }
+
+    #[test]
+    fn stream_invalid_utf8() {
+        let mut stream = StreamLexer::new(new_lexer_state(), SlowReader(b"abc \xff"));
+        assert_eq!("abc", stream.next_token().expect("error").get_value());
+        match stream.next_token() {
+            Err(LexError::Io { kind, .. }) => assert_eq!(ErrorKind::InvalidData, kind),
+            _ => panic!("error"),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+// 对源码的一次修改：将 [begin, end) 范围内的文本替换为 text
+//
+// begin 和 end 均为修改前源码中的字节偏移量。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    begin: usize,
+    end: usize,
+    text: String,
+}
+
+impl TextEdit {
+    pub fn new(begin: usize, end: usize, text: &str) -> Self {
+        TextEdit {
+            begin,
+            end: end.max(begin),
+            text: String::from(text),
+        }
+    }
+
+    pub fn get_begin(&self) -> usize {
+        self.begin
+    }
+
+    pub fn get_end(&self) -> usize {
+        self.end
+    }
+
+    pub fn get_text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    // 修改后源码长度的变化量
+    pub fn get_delta(&self) -> isize {
+        self.text.len() as isize - (self.end - self.begin) as isize
+    }
+
+    // 将修改应用到源码上，范围超出源码或不在字符边界上时返回 None
+    pub fn apply(&self, src: &str) -> Option<String> {
+        let mut edited = String::with_capacity(src.len().saturating_add_signed(self.get_delta()));
+        edited.push_str(src.get(..self.begin)?);
+        edited.push_str(self.text.as_str());
+        edited.push_str(src.get(self.end..)?);
+
+        Some(edited)
+    }
+}
@@ -1,35 +1,163 @@
-use super::{Location, Position};
-use std::string::String;
+use super::{Location, TokenKind};
+use std::borrow::Cow;
+use std::fmt::{Debug, Formatter, Result};
 
-#[derive(Clone)]
-pub struct Token {
-    token_type: String,
-    token_value: String,
+// Token 的值通常直接借用自源码，避免在解析过程中分配内存；
+// 需要在源码释放后继续使用时，可以通过 into_owned 转换为 OwnedToken
+// H 为源码的类型，ByteLexerState 产生的 Token 为 ByteToken
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "K: TokenKind, H: serde::Serialize",
+        deserialize = "K: TokenKind, H::Owned: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Token<'src, K = &'static str, H: ?Sized + ToOwned = str> {
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_kind"))]
+    token_type: K,
+    token_value: Cow<'src, H>,
     token_location: Option<Location>,
 }
 
-impl Token {
-    pub fn new(token_type: &str, token_value: &str) -> Self {
+pub type OwnedToken<K = &'static str, H = str> = Token<'static, K, H>;
+
+impl<'src, K: TokenKind, H: ?Sized + ToOwned> Token<'src, K, H> {
+    pub fn new(token_type: K, token_value: &'src H) -> Self {
+        Token {
+            token_type,
+            token_value: Cow::Borrowed(token_value),
+            token_location: None,
+        }
+    }
+
+    pub fn new_owned(token_type: K, token_value: H::Owned) -> Self {
         Token {
-            token_type: String::from(token_type),
-            token_value: String::from(token_value),
+            token_type,
+            token_value: Cow::Owned(token_value),
             token_location: None,
         }
     }
 
-    pub fn set_location(&mut self, begin_position: Position, end_position: Position) {
-        self.token_location = Some(Location::new(begin_position, end_position));
+    pub fn set_location(&mut self, location: Location) {
+        self.token_location = Some(location);
     }
 
-    pub fn get_type(&self) -> &str {
-        self.token_type.as_str()
+    pub fn get_type(&self) -> K {
+        self.token_type
     }
 
-    pub fn get_value(&self) -> &str {
-        self.token_value.as_str()
+    pub fn get_value(&self) -> &H {
+        self.token_value.as_ref()
+    }
+
+    pub fn has_location(&self) -> bool {
+        self.token_location.is_some()
     }
 
     pub fn get_location(&self) -> Location {
         self.token_location.unwrap_or(Location::new_zero())
     }
+
+    // 移动 Token 的位置，参见 Position::shift
+    pub fn shift(&mut self, line: usize, lines: isize, columns: isize, offset: isize) {
+        if let Some(location) = self.token_location.as_mut() {
+            *location = location.shift(line, lines, columns, offset);
+        }
+    }
+
+    // Token 的值是否借用自源码
+    pub fn is_borrowed(&self) -> bool {
+        match self.token_value {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        }
+    }
+
+    // 转换为不借用源码的 Token
+    pub fn into_owned(self) -> OwnedToken<K, H> {
+        Token {
+            token_type: self.token_type,
+            token_value: Cow::Owned(self.token_value.into_owned()),
+            token_location: self.token_location,
+        }
+    }
+}
+
+#[cfg(feature = "nfc")]
+impl<'src, K: TokenKind> Token<'src, K> {
+    // 将 Token 的值规范化为 NFC，已经是 NFC 时不会复制
+    pub fn into_nfc(self) -> Self {
+        use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+        if is_nfc_quick(self.token_value.chars()).eq(&IsNormalized::Yes) {
+            return self;
+        }
+
+        Token {
+            token_value: Cow::Owned(self.token_value.nfc().collect()),
+            ..self
+        }
+    }
+}
+
+// H 不是 Sized，无法通过 derive 实现以下 trait
+impl<K: Clone, H: ?Sized + ToOwned> Clone for Token<'_, K, H> {
+    fn clone(&self) -> Self {
+        Token {
+            token_type: self.token_type.clone(),
+            token_value: self.token_value.clone(),
+            token_location: self.token_location,
+        }
+    }
+}
+
+impl<K: Debug, H: ?Sized + ToOwned + Debug> Debug for Token<'_, K, H>
+where
+    H::Owned: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.debug_struct("Token")
+            .field("token_type", &self.token_type)
+            .field("token_value", &self.token_value)
+            .field("token_location", &self.token_location)
+            .finish()
+    }
+}
+
+impl<K: PartialEq, H: ?Sized + ToOwned + PartialEq> PartialEq for Token<'_, K, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.token_type.eq(&other.token_type)
+            && self.token_value.eq(&other.token_value)
+            && self.token_location.eq(&other.token_location)
+    }
+}
+
+impl<K: Eq, H: ?Sized + ToOwned + Eq> Eq for Token<'_, K, H> {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{LexerState, TokenFactory};
+
+    #[test]
+    fn borrowed_token() {
+        let mut state = LexerState::new("init", ());
+        state
+            .state("init")
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token));
+
+        let owned = {
+            let src = String::from("name");
+            let token = state.next(src.as_str()).expect("error");
+            assert!(token.is_borrowed());
+            assert!(std::ptr::eq(src.as_ptr(), token.get_value().as_ptr()));
+
+            token.into_owned()
+        };
+
+        assert!(!owned.is_borrowed());
+        assert_eq!("name", owned.get_type());
+        assert_eq!("name", owned.get_value());
+        assert_eq!(4, owned.get_location().get_end().get_offset());
+    }
 }
@@ -0,0 +1,18 @@
+use super::Token;
+
+// 规则匹配后执行的动作，H 为源码的类型
+pub enum TokenAction<'a, K = &'static str, H: ?Sized + ToOwned = str> {
+    // 产生一个 Token
+    Token(Token<'a, K, H>),
+    // 产生零个或多个 Token，之后的 Token 在后续调用 LexerState::next 时依次返回
+    Emit(Vec<Token<'a, K, H>>),
+    // 跳过匹配的文本，不产生 Token
+    Skip,
+    // 放弃本次匹配，改用下一个可以匹配的规则（与 flex 的 REJECT 一致）
+    Reject,
+    // 产生一个词法错误
+    Error(String),
+}
+
+// ByteLexerState 的规则匹配后执行的动作
+pub type ByteTokenAction<'a, K = &'static str> = TokenAction<'a, K, [u8]>;
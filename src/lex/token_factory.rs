@@ -1,15 +1,15 @@
-use super::{NextStateChange, StateChange, Token};
+use super::{Haystack, NextStateChange, StateChange, StateId, Token, TokenAction, TokenKind};
 
-pub struct TokenFactory<'t> {
-    token_type: &'t str,
+pub struct TokenFactory<K = &'static str> {
+    token_type: K,
 
     increment_offset: Option<usize>,
     increment_lines: Option<usize>,
     next_state: Vec<NextStateChange>,
 }
 
-impl<'t> TokenFactory<'t> {
-    pub fn new(token_type: &'t str) -> Self {
+impl<K: TokenKind> TokenFactory<K> {
+    pub fn new(token_type: K) -> Self {
         TokenFactory {
             token_type,
 
@@ -31,8 +31,8 @@ impl<'t> TokenFactory<'t> {
         self
     }
 
-    pub fn push_state(&mut self, state: &'static str) -> &mut Self {
-        self.next_state.push(NextStateChange::Push(state));
+    pub fn push_state(&mut self, state: impl Into<StateId>) -> &mut Self {
+        self.next_state.push(NextStateChange::Push(state.into()));
 
         self
     }
@@ -43,14 +43,84 @@ impl<'t> TokenFactory<'t> {
         self
     }
 
-    pub fn build(&self, token: &str) -> (Token, StateChange) {
+    pub fn replace_state(&mut self, state: impl Into<StateId>) -> &mut Self {
+        self.next_state.push(NextStateChange::Replace(state.into()));
+
+        self
+    }
+
+    pub fn pop_to_state(&mut self, state: impl Into<StateId>) -> &mut Self {
+        self.next_state.push(NextStateChange::PopTo(state.into()));
+
+        self
+    }
+
+    pub fn reset_state(&mut self) -> &mut Self {
+        self.next_state.push(NextStateChange::Reset);
+
+        self
+    }
+
+    pub fn begin_state(&mut self, state: impl Into<StateId>) -> &mut Self {
+        self.next_state.push(NextStateChange::Begin(state.into()));
+
+        self
+    }
+
+    // len: 匹配文本的字节数
+    fn state_change(&self, len: usize) -> StateChange {
+        StateChange::new(
+            self.increment_offset.unwrap_or(len),
+            self.increment_lines.unwrap_or(0),
+            self.next_state.clone(),
+        )
+    }
+
+    // 产生一个 Token
+    pub fn build<'a, H: ?Sized + Haystack>(
+        &self,
+        token: &'a H,
+    ) -> (TokenAction<'a, K, H>, StateChange) {
+        (
+            TokenAction::Token(Token::new(self.token_type, token)),
+            self.state_change(token.as_bytes().len()),
+        )
+    }
+
+    // 产生多个 Token，没有设定位置的 Token 将使用整个匹配文本的位置
+    pub fn emit<'a, H: ?Sized + Haystack>(
+        &self,
+        token: &H,
+        tokens: Vec<Token<'a, K, H>>,
+    ) -> (TokenAction<'a, K, H>, StateChange) {
+        (
+            TokenAction::Emit(tokens),
+            self.state_change(token.as_bytes().len()),
+        )
+    }
+
+    // 跳过匹配的文本
+    pub fn skip<'a, H: ?Sized + Haystack>(
+        &self,
+        token: &H,
+    ) -> (TokenAction<'a, K, H>, StateChange) {
+        (TokenAction::Skip, self.state_change(token.as_bytes().len()))
+    }
+
+    // 放弃本次匹配，改用下一个可以匹配的规则
+    pub fn reject<'a, H: ?Sized + Haystack>(&self) -> (TokenAction<'a, K, H>, StateChange) {
+        (TokenAction::Reject, StateChange::new(0, 0, Vec::new()))
+    }
+
+    // 产生一个词法错误，匹配的文本仍然会被跳过
+    pub fn error<'a, H: ?Sized + Haystack>(
+        &self,
+        token: &H,
+        message: &str,
+    ) -> (TokenAction<'a, K, H>, StateChange) {
         (
-            Token::new(self.token_type, token),
-            StateChange::new(
-                self.increment_offset.unwrap_or(token.len()),
-                self.increment_lines.unwrap_or(0),
-                self.next_state.clone(),
-            ),
+            TokenAction::Error(String::from(message)),
+            self.state_change(token.as_bytes().len()),
         )
     }
 }
@@ -0,0 +1,115 @@
+use super::Interner;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+// Token 的类型
+//
+// 默认使用字符串（&'static str）作为 Token 的类型，也可以通过
+// token_kinds! 宏声明一个枚举作为 Token 的类型，比较时不再需要
+// 逐个比较字符串。
+pub trait TokenKind: Copy + Eq + Hash + Debug {
+    // 类型的名称，用于错误信息等
+    fn name(&self) -> &'static str;
+
+    // 由名称得到类型，用于反序列化快照，不支持时返回 None
+    fn from_name(name: &str) -> Option<Self> {
+        let _ = name;
+
+        None
+    }
+}
+
+impl TokenKind for &'static str {
+    fn name(&self) -> &'static str {
+        self
+    }
+
+    // 只接受已经登记在全局名称表（参见 Interner）中的名称，不会登记新的名称，
+    // 因此反序列化不可信的输入不会使名称表无限增长。词法规格中的 Token 类型
+    // 在解析规格时登记，序列化时也会登记所序列化的 Token 类型
+    fn from_name(name: &str) -> Option<Self> {
+        Interner::lookup(name).map(Interner::get_name)
+    }
+}
+
+// 声明一个实现了 TokenKind 的枚举
+//
+// token_kinds! {
+//     pub enum CalcKind {
+//         Number => "number",
+//         Name => "name",
+//     }
+// }
+#[macro_export]
+macro_rules! token_kinds {
+    ($(#[$meta: meta])* $vis: vis enum $kind: ident {
+        $($(#[$variant_meta: meta])* $variant: ident => $name: literal),* $(,)?
+    }) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        $vis enum $kind {
+            $($(#[$variant_meta])* $variant),*
+        }
+
+        impl $crate::TokenKind for $kind {
+            fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name),*
+                }
+            }
+
+            fn from_name(name: &str) -> ::std::option::Option<Self> {
+                match name {
+                    $($name => ::std::option::Option::Some(Self::$variant),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+
+        impl ::std::fmt::Display for $kind {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str($crate::TokenKind::name(self))
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{LexerState, Token, TokenFactory};
+    use super::*;
+
+    token_kinds! {
+        enum CalcKind {
+            Number => "number",
+            Name => "name",
+            Eof => "eof",
+        }
+    }
+
+    #[test]
+    fn enum_kinds() {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new(CalcKind::Eof, ""))
+            .set_ignore(r"^\s+")
+            .state("init")
+            .token(r"^\d+", |_, token| {
+                TokenFactory::new(CalcKind::Number).build(token)
+            })
+            .token(r"^\w+", |_, token| {
+                TokenFactory::new(CalcKind::Name).build(token)
+            });
+
+        let kinds: Vec<_> = state
+            .tokens("foo 42")
+            .map(|token| token.expect("error").get_type())
+            .collect();
+        assert_eq!(vec![CalcKind::Name, CalcKind::Number, CalcKind::Eof], kinds);
+        assert_eq!("number", CalcKind::Number.name());
+        assert_eq!("name", CalcKind::Name.to_string());
+        assert_eq!(Some(CalcKind::Eof), CalcKind::from_name("eof"));
+        assert_eq!(None, CalcKind::from_name("other"));
+        assert_eq!(Some("number"), <&str>::from_name("number"));
+    }
+}
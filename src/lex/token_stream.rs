@@ -0,0 +1,142 @@
+use super::{CustomCheckpoint, Haystack, LexError, LexerState, Token, TokenKind};
+
+// 将 LexerState 与源码绑定，以迭代器的方式获取 Token
+//
+// 迭代在产生终止Token或者发生错误后结束。
+pub struct TokenStream<'l, 's, S, K = &'static str, H: ?Sized + Haystack = str> {
+    lexer: &'l mut LexerState<S, K, H>,
+    src: &'s H,
+    finished: bool,
+}
+
+impl<'l, 's, S, K: TokenKind, H: ?Sized + Haystack> TokenStream<'l, 's, S, K, H> {
+    pub fn new(lexer: &'l mut LexerState<S, K, H>, src: &'s H) -> Self {
+        TokenStream {
+            lexer,
+            src,
+            finished: false,
+        }
+    }
+
+    pub fn get_lexer(&self) -> &LexerState<S, K, H> {
+        self.lexer
+    }
+
+    pub fn get_src(&self) -> &'s H {
+        self.src
+    }
+
+    // 查看下一个Token，不移动文本指针
+    pub fn peek(&mut self) -> Option<Result<Token<'s, K, H>, LexError>> {
+        self.peek_nth(0)
+    }
+
+    // 查看之后的第 n 个Token（从 0 开始），不移动文本指针
+    //
+    // 通过 LexerState::dump 和 LexerState::restore 实现，
+    // Token 构造方法对自定义状态的修改不会被还原。
+    pub fn peek_nth(&mut self, n: usize) -> Option<Result<Token<'s, K, H>, LexError>> {
+        if self.finished {
+            return None;
+        }
+
+        let snapshot = self.lexer.dump();
+        let result = self.advance_nth(n);
+        self.lexer.restore(&snapshot);
+
+        result
+    }
+
+    // 与 peek_nth 相同，但 Token 构造方法对自定义状态的修改同样会通过
+    // CustomCheckpoint 还原
+    pub fn peek_nth_checkpoint(&mut self, n: usize) -> Option<Result<Token<'s, K, H>, LexError>>
+    where
+        S: CustomCheckpoint,
+    {
+        if self.finished {
+            return None;
+        }
+
+        let snapshot = self.lexer.dump_checkpoint();
+        let result = self.advance_nth(n);
+        self.lexer.restore_checkpoint(&snapshot);
+
+        result
+    }
+
+    // 获取之后的第 n 个Token，调用者负责之后还原词法解析器
+    fn advance_nth(&mut self, n: usize) -> Option<Result<Token<'s, K, H>, LexError>> {
+        let mut result = None;
+        for _ in 0..=n {
+            result = self.next();
+            if result.is_none() {
+                break;
+            }
+        }
+        self.finished = false;
+
+        result
+    }
+}
+
+impl<'l, 's, S, K: TokenKind, H: ?Sized + Haystack> Iterator for TokenStream<'l, 's, S, K, H> {
+    type Item = Result<Token<'s, K, H>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.lexer.next(self.src);
+        self.finished = result.is_err() || self.lexer.is_eof();
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::TokenFactory;
+    use super::*;
+
+    fn new_lexer_state() -> LexerState<()> {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(r"^\s+")
+            .state("init")
+            .token(r"^\w+", |_, token| TokenFactory::new("name").build(token));
+        state
+    }
+
+    fn values(tokens: Vec<Result<Token, LexError>>) -> Vec<String> {
+        tokens
+            .into_iter()
+            .map(|token| String::from(token.expect("error").get_value()))
+            .collect()
+    }
+
+    #[test]
+    fn stream_iterate() {
+        let mut state = new_lexer_state();
+        let tokens: Vec<_> = TokenStream::new(&mut state, "foo bar").collect();
+        assert_eq!(vec!["foo", "bar", ""], values(tokens));
+
+        let mut state = new_lexer_state();
+        let tokens: Vec<_> = state.tokens("foo ?bar").collect();
+        assert_eq!(2, tokens.len());
+        assert!(tokens[1].is_err());
+    }
+
+    #[test]
+    fn stream_peek() {
+        let mut state = new_lexer_state();
+        let mut stream = state.tokens("foo bar");
+
+        assert_eq!("bar", stream.peek_nth(1).unwrap().unwrap().get_value());
+        assert_eq!("", stream.peek_nth(2).unwrap().unwrap().get_value());
+        assert!(stream.peek_nth(3).is_none());
+        assert_eq!("foo", stream.peek().unwrap().unwrap().get_value());
+        assert_eq!(vec!["foo", "bar", ""], values(stream.collect()));
+    }
+}
@@ -0,0 +1,143 @@
+use super::{NextStateChange, Position, StateId};
+use std::fmt::{Debug, Display, Formatter, Result};
+
+// 词法解析过程中的调试事件，由 LexerState::set_trace 设定的回调接收
+// H 为源码的类型
+#[derive(Debug, PartialEq, Eq)]
+pub enum TraceEvent<'a, H: ?Sized = str> {
+    // 状态改变，from 和 to 分别为改变前后的当前状态
+    StateChange {
+        position: Position,
+        change: NextStateChange,
+        from: StateId,
+        to: StateId,
+    },
+    // 尝试当前状态下的一个规则，按照匹配策略尝试规则的顺序产生，
+    // 选中的规则放弃匹配后，剩余的规则将再次被尝试
+    // pattern: 规则的正则表达式
+    // token: 规则匹配的文本，不能匹配时为 None
+    RuleAttempt {
+        position: Position,
+        state: StateId,
+        rule: usize,
+        pattern: &'a str,
+        token: Option<&'a H>,
+    },
+    // 按照匹配策略选中的规则的动作接受了匹配的文本
+    RuleMatch {
+        position: Position,
+        state: StateId,
+        rule: usize,
+        len: usize,
+    },
+    // 选中的规则的动作放弃了匹配，将选择下一个可以匹配的规则
+    RuleReject {
+        position: Position,
+        state: StateId,
+        rule: usize,
+    },
+    // 没有任何规则能够匹配
+    NoMatch {
+        position: Position,
+        state: StateId,
+    },
+    // 按照恢复策略跳过了无法匹配的文本
+    Recover {
+        position: Position,
+        state: StateId,
+        len: usize,
+    },
+    // 跳过了忽略字符或注释
+    Ignore {
+        position: Position,
+        state: StateId,
+        len: usize,
+    },
+    // 产生了 EOF
+    Eof {
+        position: Position,
+        state: StateId,
+    },
+}
+
+// H 不是 Sized，无法通过 derive 实现 Clone 和 Copy
+impl<H: ?Sized> Clone for TraceEvent<'_, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H: ?Sized> Copy for TraceEvent<'_, H> {}
+
+impl<H: ?Sized> TraceEvent<'_, H> {
+    pub fn get_position(&self) -> Position {
+        match *self {
+            TraceEvent::StateChange { position, .. }
+            | TraceEvent::RuleAttempt { position, .. }
+            | TraceEvent::RuleMatch { position, .. }
+            | TraceEvent::RuleReject { position, .. }
+            | TraceEvent::NoMatch { position, .. }
+            | TraceEvent::Recover { position, .. }
+            | TraceEvent::Ignore { position, .. }
+            | TraceEvent::Eof { position, .. } => position,
+        }
+    }
+
+    // 事件发生时的当前状态，状态改变事件为改变前的状态
+    pub fn get_state(&self) -> StateId {
+        match *self {
+            TraceEvent::StateChange { from, .. } => from,
+            TraceEvent::RuleAttempt { state, .. }
+            | TraceEvent::RuleMatch { state, .. }
+            | TraceEvent::RuleReject { state, .. }
+            | TraceEvent::NoMatch { state, .. }
+            | TraceEvent::Recover { state, .. }
+            | TraceEvent::Ignore { state, .. }
+            | TraceEvent::Eof { state, .. } => state,
+        }
+    }
+}
+
+impl<H: ?Sized + Debug> Display for TraceEvent<'_, H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let position = self.get_position();
+        write!(
+            f,
+            "{}:{} <{}> ",
+            position.get_line(),
+            position.get_column(),
+            self.get_state()
+        )?;
+
+        match *self {
+            TraceEvent::StateChange { change, to, .. } => {
+                write!(f, "state {:?} -> <{}>", change, to)
+            }
+            TraceEvent::RuleAttempt {
+                rule,
+                pattern,
+                token,
+                ..
+            } => {
+                write!(f, "try rule {} /{}/", rule, pattern)?;
+                match token {
+                    Some(token) => write!(f, " on {:?}", token),
+                    None => write!(f, ", no match"),
+                }
+            }
+            TraceEvent::RuleMatch { rule, len, .. } => {
+                write!(f, "match rule {} ({} bytes)", rule, len)
+            }
+            TraceEvent::RuleReject { rule, .. } => write!(f, "reject rule {}", rule),
+            TraceEvent::NoMatch { .. } => write!(f, "no match"),
+            TraceEvent::Recover { len, .. } => write!(f, "recover {} bytes", len),
+            TraceEvent::Ignore { len, .. } => write!(f, "ignore {} bytes", len),
+            TraceEvent::Eof { .. } => write!(f, "eof"),
+        }
+    }
+}
+
+// 将调试事件逐行输出到标准错误，可直接作为 LexerState::set_trace 的回调
+pub fn print_trace<H: ?Sized + Debug>(event: &TraceEvent<'_, H>) {
+    eprintln!("{}", event);
+}
@@ -0,0 +1,102 @@
+/*
+ * Unicode 辅助
+ *
+ * 本文件提供可以组合进规则的正则表达式片段。片段本身不以 ^ 开头，
+ * 可以与其他表达式拼接，作为规则使用时通过 anchored 加上 ^：
+ *
+ *   .token(&anchored(XID_IDENTIFIER), |_, token| { ... })
+ *
+ * 列号按照字符计算；源码开头的 UTF-8 BOM 默认会被跳过，参见
+ * LexerState::set_skip_bom。开启 nfc 特性后，可以通过
+ * LexerState::set_normalize_nfc 将 Token 的值规范化为 NFC。
+ *
+ */
+
+// 按照 UAX #31 定义的标识符，与 Rust 一样允许以 _ 开头
+pub const XID_IDENTIFIER: &str = r"[\p{XID_Start}_]\p{XID_Continue}*";
+
+// 任意文字中的十进制数字，如 123、١٢٣
+pub const UNICODE_NUMBER: &str = r"\p{Nd}+";
+
+// Unicode 空白字符，包括不换行空格（U+00A0）等
+pub const UNICODE_WHITESPACE: &str = r"\p{White_Space}+";
+
+// 不包括换行符的 Unicode 空白字符，用于缩进模式等需要单独处理换行的场合
+pub const UNICODE_BLANK: &str = r"[\p{White_Space}&&[^\n\r]]+";
+
+// UTF-8 编码的 BOM
+pub const UTF8_BOM: &str = "\u{FEFF}";
+
+// 将片段转换为规则使用的表达式
+pub fn anchored(fragment: &str) -> String {
+    format!("^(?:{})", fragment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{LexerState, Token, TokenFactory};
+    use super::*;
+
+    fn new_state() -> LexerState<()> {
+        let mut state = LexerState::new("init", ());
+        state
+            .set_eof(|_, _| Token::new("eof", ""))
+            .set_ignore(&anchored(UNICODE_WHITESPACE))
+            .state("init")
+            .token(&anchored(XID_IDENTIFIER), |_, token| {
+                TokenFactory::new("name").build(token)
+            })
+            .token(&anchored(UNICODE_NUMBER), |_, token| {
+                TokenFactory::new("number").build(token)
+            })
+            .token(r"^=", |_, token| TokenFactory::new("eq").build(token));
+
+        state
+    }
+
+    #[test]
+    fn parse_unicode() {
+        let src = "\u{FEFF}变量 = ١٢٣\u{00A0}_x1\n";
+        let mut state = new_state();
+        for (token_type, token_value, column, offset) in [
+            ("name", "变量", 0, 3),
+            ("eq", "=", 3, 10),
+            ("number", "١٢٣", 5, 12),
+            ("name", "_x1", 9, 20),
+            ("eof", "", 0, 24),
+        ] {
+            let token = state.next(src).expect("error");
+            let begin = token.get_location().get_begin();
+            assert_eq!(token_type, token.get_type());
+            assert_eq!(token_value, token.get_value());
+            assert_eq!(column, begin.get_column());
+            assert_eq!(offset, begin.get_offset());
+        }
+
+        // 关闭 BOM 跳过后 BOM 无法匹配
+        let mut state = new_state();
+        state.set_skip_bom(false);
+        assert!(state.next(src).is_err());
+    }
+
+    #[cfg(feature = "nfc")]
+    #[test]
+    fn normalize_nfc() {
+        let src = "cafe\u{301} caf\u{E9}";
+        let mut state = new_state();
+        state.set_normalize_nfc(true);
+        for _ in 0..2 {
+            let token = state.next(src).expect("error");
+            assert_eq!("caf\u{E9}", token.get_value());
+        }
+        assert_eq!(
+            5,
+            new_state()
+                .next(src)
+                .expect("error")
+                .get_value()
+                .chars()
+                .count()
+        );
+    }
+}
@@ -31,7 +31,7 @@ impl GrammarParser {
     pub fn parse<'a>(
         &self,
         rule_key: &str,
-        token_parser: &(dyn Fn() -> Result<Token, ()> + 'a),
+        token_parser: &(dyn Fn() -> Result<Token<'a>, ()> + 'a),
     ) -> Result<Box<GrammarTreeNode>, ()> {
         // make root
         let enter_rule = self.get_rule(rule_key)?;
@@ -47,7 +47,7 @@ impl GrammarParser {
     fn parse_abnf_definition<'a>(
         &self,
         definition: &AbnfDefinition,
-        token_parser: &(dyn Fn() -> Result<Token, ()> + 'a),
+        token_parser: &(dyn Fn() -> Result<Token<'a>, ()> + 'a),
     ) -> Result<Box<GrammarTreeNode>, ()> {
         let mut node = Box::new(GrammarTreeNode::new());
 
@@ -124,7 +124,7 @@ impl GrammarParser {
     fn parse_abnf_definition_terminal<'a>(
         &self,
         definition_value: &String,
-        token_parser: &(dyn Fn() -> Result<Token, ()> + 'a),
+        token_parser: &(dyn Fn() -> Result<Token<'a>, ()> + 'a),
     ) -> Result<String, ()> {
         let token = token_parser()?;
 
@@ -0,0 +1,494 @@
+// 由 hoya 根据词法规格生成，请勿手动修改
+
+hoya::token_kinds! {
+    pub enum TokenType {
+        Number => "number",
+        Keyword => "keyword",
+        Name => "name",
+        LeftParen => "left_paren",
+        RightParen => "right_paren",
+        Operator => "operator",
+        Quote => "quote",
+        Text => "text",
+        Escape => "escape",
+        Eof => "eof",
+    }
+}
+
+// DFA 的转移表
+//
+// 0 号状态为死状态。starts 的第一个元素为匹配所有模式的起始状态，之后依次为
+// 只匹配各个模式的起始状态。matches 为 0 表示不是匹配状态，否则为匹配的模式
+// 序号加 1，匹配状态比匹配的文本晚一个字节（或源码末尾 eoi）到达。
+struct Table {
+    classes: [u8; 256],
+    class_count: usize,
+    starts: &'static [u32],
+    transitions: &'static [u32],
+    eoi: &'static [u32],
+    matches: &'static [u32],
+}
+
+// 状态改变，状态以其在 STATES 中的序号表示
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+enum Change {
+    Push(usize),
+    Pop(usize),
+    Replace(usize),
+    PopTo(usize),
+    Reset,
+    Begin(usize),
+}
+
+// 规则产生的 Token 类型（None 表示跳过匹配的文本）和之后的状态改变
+struct Rule {
+    kind: Option<TokenType>,
+    changes: &'static [Change],
+}
+
+// 从第 start 个起始状态开始在 src 开头运行 DFA，返回最后一个匹配的模式序号和匹配长度
+fn search(table: &Table, start: usize, src: &str) -> Option<(usize, usize)> {
+    let mut state = table.starts[start] as usize;
+    let mut matched = None;
+    for (offset, byte) in src.bytes().enumerate() {
+        let class = table.classes[usize::from(byte)] as usize;
+        state = table.transitions[state * table.class_count + class] as usize;
+        if state == 0 {
+            return matched;
+        }
+        if table.matches[state] != 0 {
+            matched = Some((table.matches[state] as usize - 1, offset));
+        }
+    }
+
+    state = table.eoi[state] as usize;
+    if table.matches[state] != 0 {
+        matched = Some((table.matches[state] as usize - 1, src.len()));
+    }
+
+    matched
+}
+
+// 在 src 开头运行 DFA，返回第一个匹配非空串的模式序号和匹配长度
+fn find(table: &Table, src: &str) -> Option<(usize, usize)> {
+    let non_empty = |start| search(table, start, src).filter(|(_, len)| *len != 0);
+
+    // 从匹配所有模式的起始状态开始时，DFA 的结果即为第一个能够匹配的模式，
+    // 匹配到空串时需要逐个尝试之后的模式
+    non_empty(0).or_else(|| (1..table.starts.len()).find_map(non_empty))
+}
+
+// 由 DFA 表和规则表驱动的词法解析器
+pub struct Lexer {
+    state: usize,
+    state_stack: Vec<usize>,
+    offset: usize,
+    line: usize,
+    column: usize,
+    is_eof: bool,
+}
+
+impl Default for Lexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[allow(dead_code)]
+impl Lexer {
+    pub fn new() -> Self {
+        Lexer {
+            state: 0,
+            state_stack: Vec::new(),
+            offset: 0,
+            line: 1,
+            column: 0,
+            is_eof: false,
+        }
+    }
+
+    pub fn get_current_state(&self) -> &'static str {
+        STATES[self.state]
+    }
+
+    // 当前文本指针所在的位置
+    pub fn get_current_position(&self) -> hoya::Position {
+        hoya::Position::new(self.line, self.column, self.offset)
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.is_eof
+    }
+
+    // 以迭代器的方式获取 Token，迭代在产生终止Token或者发生错误后结束
+    pub fn tokens<'a, 's: 'a>(
+        &'a mut self,
+        src: &'s str,
+    ) -> impl Iterator<Item = Result<hoya::Token<'s, TokenType>, hoya::LexError>> + 'a {
+        let mut finished = false;
+
+        std::iter::from_fn(move || {
+            if finished {
+                return None;
+            }
+
+            let result = self.next(src);
+            finished = result.is_err() || self.is_eof;
+
+            Some(result)
+        })
+    }
+
+    // 从源码中获取一个Token
+    pub fn next<'s>(
+        &mut self,
+        src: &'s str,
+    ) -> Result<hoya::Token<'s, TokenType>, hoya::LexError> {
+        loop {
+            if self.is_eof {
+                break Ok(self.next_eof());
+            }
+
+            self.skip_bom(src);
+            if self.offset >= src.len() || self.skip_ignore(src) {
+                break Ok(self.next_eof());
+            }
+
+            let rest = &src[self.offset..];
+            let begin = self.get_current_position();
+            let (index, len) = match DFAS[self.state].and_then(|table| find(table, rest)) {
+                Some(matched) => matched,
+                None => {
+                    break Err(hoya::LexError::NoMatch {
+                        position: begin,
+                        state: STATES[self.state],
+                        snippet: hoya::LexError::snippet(rest),
+                    })
+                }
+            };
+
+            let rule = &RULES[self.state][index];
+            rule.changes
+                .iter()
+                .for_each(|change| self.change_state(*change));
+            self.advance(src, len);
+
+            if let Some(kind) = rule.kind {
+                let mut token = hoya::Token::new(kind, &rest[..len]);
+                token.set_location(hoya::Location::new(begin, self.get_current_position()));
+
+                break Ok(token);
+            }
+        }
+    }
+
+    fn next_eof(&mut self) -> hoya::Token<'static, TokenType> {
+        self.is_eof = true;
+
+        let position = self.get_current_position();
+        let mut token = hoya::Token::new(TokenType::Eof, "");
+        token.set_location(hoya::Location::new(position, position));
+
+        token
+    }
+
+    // 在源码开头跳过 UTF-8 BOM
+    fn skip_bom(&mut self, src: &str) {
+        if self.offset == 0 && src.starts_with('\u{feff}') {
+            self.offset = '\u{feff}'.len_utf8();
+        }
+    }
+
+    // 跳过忽略字符和注释，返回是否已到达源码末尾
+    fn skip_ignore(&mut self, src: &str) -> bool {
+        while let Some((_, len)) = IGNORE.and_then(|table| find(table, &src[self.offset..])) {
+            self.advance(src, len);
+        }
+
+        self.offset >= src.len()
+    }
+
+    // 改变当前状态，状态栈中没有足够的状态时与 LexerState 一样忽略
+    fn change_state(&mut self, change: Change) {
+        match change {
+            Change::Push(state) => {
+                self.state_stack.push(self.state);
+                self.state = state;
+            }
+            Change::Pop(times) => {
+                for _ in 0..times {
+                    match self.state_stack.pop() {
+                        Some(state) => self.state = state,
+                        None => break,
+                    }
+                }
+            }
+            Change::Replace(state) => self.state = state,
+            Change::PopTo(state) => {
+                if self.state != state {
+                    if let Some(index) = self
+                        .state_stack
+                        .iter()
+                        .rposition(|stacked| *stacked == state)
+                    {
+                        self.state_stack.truncate(index);
+                        self.state = state;
+                    }
+                }
+            }
+            Change::Reset => {
+                self.state_stack.clear();
+                self.state = 0;
+            }
+            Change::Begin(state) => {
+                self.state_stack.clear();
+                self.state = state;
+            }
+        }
+    }
+
+    // 向前移动文本指针，并更新行号和列号，列号按照字符计算
+    fn advance(&mut self, src: &str, len: usize) {
+        let begin = self.offset;
+        self.offset += len;
+
+        // \r\n 在遇到 \r 时已经计为一行，其后的 \n 不再重复计算
+        let mut after_cr = src[..begin].ends_with('\r');
+        for c in src[begin..self.offset].chars() {
+            match c {
+                '\n' if after_cr => {}
+                '\n' | '\r' => {
+                    self.line += 1;
+                    self.column = 0;
+                }
+                _ => self.column += 1,
+            }
+            after_cr = c == '\r';
+        }
+    }
+}
+
+static STATES: [&str; 2] = [
+    "INITIAL",
+    "STRING",
+];
+
+// 状态 INITIAL 的 DFA
+static DFA_0: Table = Table {
+    classes: [
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 1, 0, 0, 0, 0, 0, 2, 3, 4, 4, 0, 4, 5, 4,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 0, 0, 0, 4, 0, 0,
+        0, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+        7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 0, 0, 0, 0, 7,
+        0, 7, 7, 7, 7, 8, 7, 7, 7, 7, 7, 7, 9, 7, 7, 7,
+        7, 7, 7, 7, 10, 7, 7, 7, 7, 7, 7, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ],
+    class_count: 11,
+    starts: &[
+        1, 2, 3, 4, 5, 6, 7, 8,
+    ],
+    transitions: &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 9, 10, 11, 12,
+        0, 13, 14, 14, 15, 14, 0, 0, 0, 0, 0, 0, 13, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 0, 0,
+        0, 0, 0, 14, 14, 14, 14, 0, 0, 10, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 12, 0, 0, 0, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 17, 18, 18,
+        18, 18, 18, 18, 18, 18, 18, 18, 18, 19, 19, 19, 19, 19, 19, 19,
+        19, 19, 19, 19, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 20, 21,
+        21, 21, 21, 21, 22, 23, 21, 21, 21, 21, 24, 24, 24, 24, 24, 24,
+        25, 25, 25, 25, 25, 24, 24, 24, 24, 24, 24, 25, 25, 26, 25, 25,
+        0, 0, 0, 0, 0, 0, 0, 0, 27, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 28, 0, 0, 0, 0, 21, 21, 21,
+        21, 21, 22, 23, 21, 21, 21, 21, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 24, 24, 24, 24, 24, 24, 25, 25, 25, 25, 25, 24, 24,
+        24, 24, 24, 24, 25, 25, 25, 25, 29, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 30, 21, 21, 21, 21, 21, 21, 31, 21, 21, 21, 21, 32,
+        32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32,
+        32, 32, 32, 32, 32, 21, 21, 21, 21, 21, 21, 31, 21, 21, 21, 21,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ],
+    eoi: &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 17, 18, 19, 20, 21, 24, 24,
+        0, 0, 0, 0, 0, 0, 0, 21, 0, 24, 24, 0, 21, 32, 32, 21,
+        0,
+    ],
+    matches: &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 7, 4, 5, 6, 1, 1, 1, 3, 3, 3, 0, 0, 3, 0, 1,
+        2,
+    ],
+};
+
+// 状态 STRING 的 DFA
+static DFA_1: Table = Table {
+    classes: [
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4,
+        5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+        7, 7, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+        8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+        9, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 11, 10, 10,
+        12, 13, 13, 13, 14, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+    ],
+    class_count: 15,
+    starts: &[
+        1, 2, 3, 4,
+    ],
+    transitions: &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5,
+        5, 6, 7, 0, 0, 0, 0, 8, 9, 10, 11, 12, 13, 14, 5, 5,
+        0, 0, 0, 0, 0, 0, 8, 9, 10, 11, 12, 13, 14, 0, 0, 0,
+        7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 15, 15, 16, 16, 16,
+        16, 16, 16, 17, 18, 19, 20, 21, 22, 23, 24, 24, 24, 24, 24, 24,
+        24, 24, 24, 24, 24, 24, 24, 24, 24, 25, 0, 25, 25, 0, 0, 0,
+        0, 26, 27, 28, 29, 30, 31, 32, 0, 0, 0, 0, 5, 5, 5, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 8, 8, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 8, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 10, 10, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 10, 10, 10, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 15, 15, 16, 16, 16, 16, 16, 16, 17, 18, 19, 20, 21, 22, 23,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 5, 5, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 8, 8, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        8, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        10, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 10, 10,
+        10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 10, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 33, 33, 33, 33, 33, 33, 33, 33, 33,
+        33, 33, 33, 33, 33, 33, 0, 0, 0, 0, 25, 25, 25, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 26, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 26, 26, 26, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 26, 26, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 28, 28, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 28, 28, 28, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 28, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ],
+    eoi: &[
+        0, 0, 0, 0, 0, 16, 24, 0, 0, 0, 0, 0, 0, 0, 0, 16,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 33, 0, 0, 0, 0, 0, 0,
+        0, 0,
+    ],
+    matches: &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 3, 0, 0, 0, 0, 0, 0, 0,
+        0, 2,
+    ],
+};
+
+// 忽略字符和注释的 DFA
+static DFA_IGNORE: Table = Table {
+    classes: [
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 0, 0, 1, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4,
+        5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6,
+        7, 7, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+        8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+        9, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 11, 10, 10,
+        12, 13, 13, 13, 14, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+    ],
+    class_count: 15,
+    starts: &[
+        1, 2, 3,
+    ],
+    transitions: &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        4, 4, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4,
+        4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 7, 7, 6,
+        6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 6, 0, 0, 0, 8, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 6, 7, 7, 6, 6, 6, 6,
+        6, 6, 6, 6, 6, 6, 6, 6, 9, 9, 10, 9, 10, 10, 10, 10,
+        11, 12, 13, 14, 15, 16, 17, 9, 9, 10, 9, 10, 10, 10, 10, 11,
+        12, 13, 14, 15, 16, 17, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 8, 8, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 18, 18, 18, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 18, 18, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 19, 19, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 19, 19, 19, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 19, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 8, 8, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 18, 18, 18, 0, 0, 0, 0, 0, 0, 0, 0,
+    ],
+    eoi: &[
+        0, 0, 0, 0, 6, 0, 0, 6, 10, 10, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0,
+    ],
+    matches: &[
+        0, 0, 0, 0, 0, 0, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2,
+        2, 2, 0, 0,
+    ],
+};
+
+static RULES: [&[Rule]; 2] = [
+    // INITIAL
+    &[
+        // 10: ^(?:(?:(?:[0-9])+(\.(?:[0-9])+)?))
+        Rule { kind: Some(TokenType::Number), changes: &[] },
+        // 11: ^(?:(?:let))
+        Rule { kind: Some(TokenType::Keyword), changes: &[] },
+        // 12: ^(?:(?:[a-zA-Z_][a-zA-Z0-9_]*))
+        Rule { kind: Some(TokenType::Name), changes: &[] },
+        // 13: ^(?:(?:\())
+        Rule { kind: Some(TokenType::LeftParen), changes: &[] },
+        // 14: ^(?:(?:\)))
+        Rule { kind: Some(TokenType::RightParen), changes: &[] },
+        // 15: ^(?:[-+*/=])
+        Rule { kind: Some(TokenType::Operator), changes: &[] },
+        // 16: ^(?:(?:"))
+        Rule { kind: Some(TokenType::Quote), changes: &[Change::Push(1)] },
+    ],
+    // STRING
+    &[
+        // 17: ^(?:[^"\\]+)
+        Rule { kind: Some(TokenType::Text), changes: &[] },
+        // 18: ^(?:\\.)
+        Rule { kind: Some(TokenType::Escape), changes: &[] },
+        // 19: ^(?:(?:"))
+        Rule { kind: Some(TokenType::Quote), changes: &[Change::Pop(1)] },
+    ],
+];
+
+static DFAS: [Option<&Table>; 2] = [Some(&DFA_0), Some(&DFA_1)];
+
+static IGNORE: Option<&Table> = Some(&DFA_IGNORE);
@@ -0,0 +1,94 @@
+use hoya::{LexError, LexerSpec, Location, TokenKind};
+
+const SPEC: &str = include_str!("data/calc.l");
+
+mod calc_lexer {
+    include!("data/calc_lexer.rs");
+}
+
+// 解释执行的 LexerState 产生的 Token 类型、值和位置
+fn interpreted(src: &str) -> Vec<Result<(&'static str, String, Location), LexError>> {
+    let mut lexer = LexerSpec::parse(SPEC).expect("invalid spec").build();
+    let tokens = lexer
+        .tokens(src)
+        .map(|token| {
+            token.map(|token| {
+                (
+                    token.get_type(),
+                    String::from(token.get_value()),
+                    token.get_location(),
+                )
+            })
+        })
+        .collect();
+
+    tokens
+}
+
+// 生成的词法解析器产生的 Token 类型、值和位置
+fn generated(src: &str) -> Vec<Result<(&'static str, String, Location), LexError>> {
+    let mut lexer = calc_lexer::Lexer::new();
+    let tokens = lexer
+        .tokens(src)
+        .map(|token| {
+            token.map(|token| {
+                (
+                    token.get_type().name(),
+                    String::from(token.get_value()),
+                    token.get_location(),
+                )
+            })
+        })
+        .collect();
+
+    tokens
+}
+
+// 规格变化后，设定 HOYA_BLESS 环境变量以重新生成 tests/data/calc_lexer.rs
+#[cfg(feature = "generator")]
+#[test]
+fn generated_source() {
+    const GENERATED: &str = "tests/data/calc_lexer.rs";
+
+    let spec = LexerSpec::parse(SPEC).expect("invalid spec");
+    let source = hoya::LexerGenerator::new(&spec)
+        .generate()
+        .expect("generate");
+
+    if std::env::var_os("HOYA_BLESS").is_some() {
+        std::fs::write(GENERATED, &source).expect("write generated lexer");
+    }
+    assert_eq!(std::fs::read_to_string(GENERATED).expect("read"), source);
+}
+
+#[test]
+fn generated_tokens() {
+    for src in [
+        "let x = (1.5 + y2) * 3 // comment\n\"a\\\"b\" / 4",
+        "\u{feff}letter\r\n\r\n  \"多字节\\n\"\r// end",
+        "a\n\"unterminated",
+        "1 + # 2",
+        "\"\\é\" 1.5.6 let_",
+        "",
+    ] {
+        assert_eq!(interpreted(src), generated(src), "{:?}", src);
+    }
+
+    let tokens = generated("let x = (1.5 + y2) * 3 // comment\n\"a\\\"b\" / 4");
+    assert_eq!(18, tokens.len());
+
+    match generated("1 + # 2").pop() {
+        Some(Err(LexError::NoMatch { state, snippet, .. })) => {
+            assert_eq!(("INITIAL", "# 2"), (state, snippet.as_str()))
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let src = "let \"";
+    let mut lexer = calc_lexer::Lexer::new();
+    let token = lexer.next(src).expect("generated");
+    assert_eq!(calc_lexer::TokenType::Keyword, token.get_type());
+    assert_eq!("INITIAL", lexer.get_current_state());
+    lexer.next(src).expect("generated");
+    assert_eq!("STRING", lexer.get_current_state());
+}